@@ -0,0 +1,31 @@
+//! Poll a sensor connected to a Linux serial port every 5 seconds.
+//!
+//! The serial port must be configured beforehand, eg:
+//!
+//! ```sh
+//! stty -F /dev/ttyUSB0 9600 cs8 -cstopb -parenb raw -echo min 0 time 10
+//! cargo run --example linux_serial --features std -- /dev/ttyUSB0
+//! ```
+//!
+//! `min 0 time 10` makes reads time out after one second.
+
+use mh_z19::driver::Mhz19Driver;
+use std::fs::OpenOptions;
+use std::thread;
+use std::time::Duration;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "/dev/ttyUSB0".to_string());
+    let port = OpenOptions::new().read(true).write(true).open(&path)?;
+    let mut driver = Mhz19Driver::new(port, 1);
+
+    loop {
+        match driver.read_co2() {
+            Ok(ppm) => println!("CO2: {} ppm", ppm),
+            Err(e) => eprintln!("Unable to read CO2 concentration: {}", e),
+        }
+        thread::sleep(Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,599 @@
+//! Reassembly of packets from a stream of bytes read from the uart.
+
+#[cfg(feature = "std")]
+use crate::driver::{Clock, SystemClock};
+use crate::{join_u16_be, parse_gas_concentration_ppm, parse_payload, Command, MHZ19Error, Packet};
+#[cfg(feature = "alloc")]
+use crate::{parse_gas_reading, GasReading};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+/// Counters of the packets seen by a [`PacketAssembler`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AssemblerStats {
+    /// Number of valid packets assembled
+    pub frames: u32,
+    /// Number of packets rejected because of a wrong checksum
+    pub checksum_failures: u32,
+    /// Number of times bytes were discarded to find the next start byte
+    pub resyncs: u32,
+    /// Number of packets made only of 0xFF or only of 0x00 bytes, read while no sensor answers
+    pub no_sensor: u32,
+}
+
+/// Default number of consecutive bytes discarded before a `ResyncFailed` error, see
+/// [`PacketAssembler::with_max_resync_bytes`].
+pub const DEFAULT_MAX_RESYNC_BYTES: usize = 256;
+
+/// Reassemble packets from bytes read one by one from the uart.
+///
+/// Bytes received before a start byte (0xFF) are discarded. When a packet has a wrong
+/// checksum, the assembler resynchronizes on the next start byte found in the rejected packet.
+/// A packet made only of 0xFF or 0x00 bytes (no sensor answering) is dropped entirely and
+/// reported as `NoSensorResponse`.
+#[derive(Debug, Clone)]
+pub struct PacketAssembler {
+    buffer: Packet,
+    len: usize,
+    discarding: bool,
+    discarded: usize,
+    max_resync_bytes: usize,
+    stats: AssemblerStats,
+}
+
+impl Default for PacketAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PacketAssembler {
+    pub const fn new() -> Self {
+        Self::with_max_resync_bytes(DEFAULT_MAX_RESYNC_BYTES)
+    }
+
+    /// Create an assembler reporting a `ResyncFailed` error once more than `max_resync_bytes`
+    /// consecutive bytes were discarded without finding a start byte, eg on a badly wired link.
+    pub const fn with_max_resync_bytes(max_resync_bytes: usize) -> Self {
+        PacketAssembler {
+            buffer: [0; 9],
+            len: 0,
+            discarding: false,
+            discarded: 0,
+            max_resync_bytes,
+            stats: AssemblerStats {
+                frames: 0,
+                checksum_failures: 0,
+                resyncs: 0,
+                no_sensor: 0,
+            },
+        }
+    }
+
+    /// Feed the next byte read from the uart.
+    ///
+    /// Returns `Some` once 9 bytes starting with a start byte have been received: either the
+    /// valid packet or the checksum error. Also returns a `ResyncFailed` error every
+    /// `max_resync_bytes` discarded bytes while no start byte is found.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Packet, MHZ19Error>> {
+        if self.len == 0 && byte != 0xFF {
+            if !self.discarding {
+                self.discarding = true;
+                self.stats.resyncs += 1;
+            }
+            self.discarded += 1;
+            if self.discarded > self.max_resync_bytes {
+                self.discarded = 0;
+                return Some(Err(MHZ19Error::ResyncFailed));
+            }
+            return None;
+        }
+        self.discarding = false;
+        self.discarded = 0;
+        self.buffer[self.len] = byte;
+        self.len += 1;
+        if self.len < self.buffer.len() {
+            return None;
+        }
+
+        let packet = self.buffer;
+        match parse_payload(&packet) {
+            Ok(_) => {
+                self.len = 0;
+                self.stats.frames += 1;
+                Some(Ok(packet))
+            }
+            Err(MHZ19Error::NoSensorResponse) => {
+                self.len = 0;
+                self.stats.no_sensor += 1;
+                Some(Err(MHZ19Error::NoSensorResponse))
+            }
+            Err(e) => {
+                self.stats.checksum_failures += 1;
+                self.stats.resyncs += 1;
+                self.resync();
+                Some(Err(e))
+            }
+        }
+    }
+
+    /// Drop the buffered bytes, keeping everything from the next start byte.
+    fn resync(&mut self) {
+        match self.buffer[1..self.len].iter().position(|b| *b == 0xFF) {
+            Some(position) => {
+                let start = position + 1;
+                self.buffer.copy_within(start..self.len, 0);
+                self.len -= start;
+            }
+            None => self.len = 0,
+        }
+    }
+
+    /// Number of bytes of the packet being assembled
+    pub fn buffered_len(&self) -> usize {
+        self.len
+    }
+
+    /// Drop the bytes of the packet being assembled.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn stats(&self) -> AssemblerStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = AssemblerStats::default();
+    }
+}
+
+/// A [`PacketAssembler`] recording when the bytes of each packet arrived, to measure latency.
+///
+/// The duration of a packet is the time elapsed between the reception of its start byte and
+/// of its last byte. After a checksum failure, the bytes kept to resynchronize are considered
+/// received with the byte which completed the rejected packet.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct TimestampingAssembler<C = SystemClock> {
+    assembler: PacketAssembler,
+    clock: C,
+    first_byte: Duration,
+    last_frame_duration: Option<Duration>,
+}
+
+#[cfg(feature = "std")]
+impl TimestampingAssembler {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock::default())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for TimestampingAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: Clock> TimestampingAssembler<C> {
+    pub fn with_clock(clock: C) -> Self {
+        TimestampingAssembler {
+            assembler: PacketAssembler::new(),
+            clock,
+            first_byte: Duration::ZERO,
+            last_frame_duration: None,
+        }
+    }
+
+    /// Feed the next byte read from the uart, see [`PacketAssembler::push`].
+    pub fn push(&mut self, byte: u8) -> Option<Result<Packet, MHZ19Error>> {
+        let now = self.clock.now();
+        let result = self.assembler.push(byte);
+        match result {
+            Some(Ok(_)) => {
+                self.last_frame_duration = Some(now.saturating_sub(self.first_byte));
+            }
+            Some(Err(_)) => self.first_byte = now,
+            None if self.assembler.buffered_len() == 1 => self.first_byte = now,
+            None => {}
+        }
+        result
+    }
+
+    /// Time taken to receive the last valid packet, `None` if no packet was assembled yet.
+    pub fn last_frame_duration(&self) -> Option<Duration> {
+        self.last_frame_duration
+    }
+
+    pub fn assembler(&self) -> &PacketAssembler {
+        &self.assembler
+    }
+}
+
+/// Iterator over the packets found in a buffer of bytes read from the uart.
+///
+/// Useful when several packets are received by a single read, eg from a large FIFO buffer.
+/// Packets are reassembled by a [`PacketAssembler`]; the bytes of a trailing incomplete packet
+/// are not consumed.
+#[derive(Debug, Clone)]
+pub struct PacketReader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+    assembler: PacketAssembler,
+}
+
+impl<'a> PacketReader<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        PacketReader {
+            buffer,
+            position: 0,
+            assembler: PacketAssembler::new(),
+        }
+    }
+
+    /// Number of bytes of the buffer processed so far, excluding the bytes of an incomplete packet.
+    ///
+    /// Once the iteration is over, the caller can drop this number of bytes from its buffer.
+    pub fn consumed(&self) -> usize {
+        self.position - self.assembler.buffered_len()
+    }
+
+    /// Iterate over the concentrations of the "read gas concentration" responses, skipping the
+    /// other valid packets (eg calibration acknowledgements).
+    pub fn ppm(&mut self) -> PpmReader<'_, 'a> {
+        PpmReader { reader: self }
+    }
+}
+
+impl<'a> Iterator for PacketReader<'a> {
+    type Item = Result<Packet, MHZ19Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(byte) = self.buffer.get(self.position) {
+            self.position += 1;
+            if let Some(result) = self.assembler.push(*byte) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+/// Iterator over the concentrations read by a [`PacketReader`], see [`PacketReader::ppm`].
+#[derive(Debug)]
+pub struct PpmReader<'r, 'a> {
+    reader: &'r mut PacketReader<'a>,
+}
+
+impl<'r, 'a> Iterator for PpmReader<'r, 'a> {
+    type Item = Result<u32, MHZ19Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for packet in self.reader.by_ref() {
+            match packet.and_then(|packet| parse_gas_concentration_ppm(&packet)) {
+                Err(MHZ19Error::WrongPacketType(_, _)) => continue,
+                result => return Some(result),
+            }
+        }
+        None
+    }
+}
+
+/// Iterator adapter keeping only the packets of a given command type.
+///
+/// The 3rd and 4th bytes of the kept packets are decoded as a concentration, as found in a
+/// "read gas concentration" response, after checking their checksum. The other packets (eg
+/// calibration acknowledgements) are skipped.
+#[derive(Debug, Clone)]
+pub struct PacketFilter<I> {
+    inner: I,
+    expected_command: u8,
+}
+
+impl<I: Iterator<Item = Packet>> Iterator for PacketFilter<I> {
+    type Item = Result<u32, MHZ19Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let expected_command = self.expected_command;
+        self.inner
+            .by_ref()
+            .find(|packet| packet[1] == expected_command)
+            .map(|packet| {
+                parse_payload(&packet).map(|payload| join_u16_be(payload[1], payload[2]) as u32)
+            })
+    }
+}
+
+/// Keep only the "read gas concentration" responses of the packets, decoded to CO2 ppm.
+pub fn filter_gas_concentration<I: Iterator<Item = Packet>>(iter: I) -> PacketFilter<I> {
+    filter_by_command(Command::ReadGasConcentration.get_command_value(), iter)
+}
+
+/// Keep only the packets of the given command byte, see [`PacketFilter`].
+pub fn filter_by_command<I: Iterator<Item = Packet>>(command_byte: u8, iter: I) -> PacketFilter<I> {
+    PacketFilter {
+        inner: iter,
+        expected_command: command_byte,
+    }
+}
+
+/// Parse all the complete "read gas concentration" responses of a buffer.
+///
+/// Returns the parsing results and the number of bytes consumed, see [`PacketReader`].
+#[cfg(feature = "alloc")]
+pub fn parse_multi_packet(buffer: &[u8]) -> (Vec<Result<GasReading, MHZ19Error>>, usize) {
+    let mut reader = PacketReader::new(buffer);
+    let readings = reader
+        .by_ref()
+        .map(|packet| packet.and_then(|packet| parse_gas_reading(&packet)))
+        .collect();
+    (readings, reader.consumed())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static GAS_CONCENTRATION_RESPONSE: Packet =
+        [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+
+    fn push_all(
+        assembler: &mut PacketAssembler,
+        bytes: &[u8],
+    ) -> Option<Result<Packet, MHZ19Error>> {
+        let mut last = None;
+        for byte in bytes {
+            if let Some(result) = assembler.push(*byte) {
+                last = Some(result);
+            }
+        }
+        last
+    }
+
+    #[test]
+    fn test_assemble() {
+        let mut assembler = PacketAssembler::new();
+        for byte in &GAS_CONCENTRATION_RESPONSE[..8] {
+            assert_eq!(None, assembler.push(*byte));
+        }
+        assert_eq!(
+            Some(Ok(GAS_CONCENTRATION_RESPONSE)),
+            assembler.push(GAS_CONCENTRATION_RESPONSE[8])
+        );
+        assert_eq!(0, assembler.buffered_len());
+        assert_eq!(
+            AssemblerStats {
+                frames: 1,
+                checksum_failures: 0,
+                resyncs: 0,
+                no_sensor: 0
+            },
+            assembler.stats()
+        );
+    }
+
+    #[test]
+    fn test_discard_noise() {
+        let mut assembler = PacketAssembler::new();
+        assert_eq!(None, push_all(&mut assembler, &[0x01, 0x02, 0x03]));
+        assert_eq!(
+            Some(Ok(GAS_CONCENTRATION_RESPONSE)),
+            push_all(&mut assembler, &GAS_CONCENTRATION_RESPONSE)
+        );
+        assert_eq!(
+            AssemblerStats {
+                frames: 1,
+                checksum_failures: 0,
+                resyncs: 1,
+                no_sensor: 0
+            },
+            assembler.stats()
+        );
+    }
+
+    #[test]
+    fn test_resync_failed() {
+        let mut assembler = PacketAssembler::with_max_resync_bytes(4);
+        assert_eq!(None, push_all(&mut assembler, &[0x00; 4]));
+        assert_eq!(Some(Err(MHZ19Error::ResyncFailed)), assembler.push(0x00));
+        // the count restarts after the error, and on each start byte
+        assert_eq!(None, push_all(&mut assembler, &[0x00; 4]));
+        assert_eq!(
+            Some(Ok(GAS_CONCENTRATION_RESPONSE)),
+            push_all(&mut assembler, &GAS_CONCENTRATION_RESPONSE)
+        );
+        assert_eq!(None, push_all(&mut assembler, &[0x00; 4]));
+        assert_eq!(2, assembler.stats().resyncs);
+
+        let mut assembler = PacketAssembler::new();
+        assert_eq!(
+            None,
+            push_all(&mut assembler, &[0x00; DEFAULT_MAX_RESYNC_BYTES])
+        );
+    }
+
+    #[test]
+    fn test_resync_after_checksum_failure() {
+        let mut assembler = PacketAssembler::new();
+        // garbage packet containing a start byte
+        assert_eq!(
+            Some(Err(MHZ19Error::WrongChecksum(0x7A, 0x02))),
+            push_all(
+                &mut assembler,
+                &[0xFF, 0x86, 0x00, 0x00, 0x01, 0x00, 0x00, 0xFF, 0x02]
+            )
+        );
+        assert_eq!(2, assembler.buffered_len());
+        // the kept bytes make the next packet invalid too, until the assembler resyncs on it
+        assert_eq!(
+            Some(Ok(GAS_CONCENTRATION_RESPONSE)),
+            push_all(&mut assembler, &GAS_CONCENTRATION_RESPONSE)
+        );
+        assert_eq!(
+            AssemblerStats {
+                frames: 1,
+                checksum_failures: 2,
+                resyncs: 2,
+                no_sensor: 0
+            },
+            assembler.stats()
+        );
+
+        assembler.reset_stats();
+        assert_eq!(AssemblerStats::default(), assembler.stats());
+    }
+
+    #[test]
+    fn test_no_sensor_response() {
+        let mut assembler = PacketAssembler::new();
+        let mut results = [0xFF; 27].iter().filter_map(|byte| assembler.push(*byte));
+        for _ in 0..3 {
+            assert_eq!(Some(Err(MHZ19Error::NoSensorResponse)), results.next());
+        }
+        assert_eq!(None, results.next());
+        assert_eq!(0, assembler.buffered_len());
+        assert_eq!(
+            AssemblerStats {
+                frames: 0,
+                checksum_failures: 0,
+                resyncs: 0,
+                no_sensor: 3
+            },
+            assembler.stats()
+        );
+        // the assembler still syncs on the next packet
+        assert_eq!(
+            Some(Ok(GAS_CONCENTRATION_RESPONSE)),
+            push_all(&mut assembler, &GAS_CONCENTRATION_RESPONSE)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_timestamping_assembler() {
+        use crate::driver::test::MockClock;
+
+        let mut assembler = TimestampingAssembler::with_clock(MockClock::default());
+        assert_eq!(None, assembler.last_frame_duration());
+        // noise is not part of the packet
+        assembler.push(0x00);
+        assembler.clock.now += Duration::from_millis(50);
+        for byte in &GAS_CONCENTRATION_RESPONSE[..8] {
+            assert_eq!(None, assembler.push(*byte));
+            assembler.clock.now += Duration::from_millis(1);
+        }
+        assembler.clock.now += Duration::from_millis(2);
+        assert_eq!(
+            Some(Ok(GAS_CONCENTRATION_RESPONSE)),
+            assembler.push(GAS_CONCENTRATION_RESPONSE[8])
+        );
+        assert_eq!(
+            Some(Duration::from_millis(10)),
+            assembler.last_frame_duration()
+        );
+
+        assembler.clock.now += Duration::from_secs(1);
+        for byte in &GAS_CONCENTRATION_RESPONSE {
+            assembler.push(*byte);
+        }
+        assert_eq!(Some(Duration::ZERO), assembler.last_frame_duration());
+        assert_eq!(2, assembler.assembler().stats().frames);
+    }
+
+    #[test]
+    fn test_packet_reader() {
+        let mut buffer = [0u8; 23];
+        buffer[1..10].copy_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        buffer[10..19].copy_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        buffer[19..].copy_from_slice(&GAS_CONCENTRATION_RESPONSE[..4]);
+
+        let mut reader = PacketReader::new(&buffer);
+        assert_eq!(Some(Ok(GAS_CONCENTRATION_RESPONSE)), reader.next());
+        assert_eq!(10, reader.consumed());
+        assert_eq!(Some(Ok(GAS_CONCENTRATION_RESPONSE)), reader.next());
+        assert_eq!(None, reader.next());
+        assert_eq!(19, reader.consumed());
+    }
+
+    #[test]
+    fn test_packet_reader_ppm() {
+        let calibration_ack = [0xFF, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
+        let mut corrupted = GAS_CONCENTRATION_RESPONSE;
+        corrupted[3] = 0x61;
+        let mut buffer = [0u8; 40];
+        buffer[..9].copy_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        buffer[9..18].copy_from_slice(&calibration_ack);
+        buffer[18..27].copy_from_slice(&corrupted);
+        buffer[27..36].copy_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        buffer[36..].copy_from_slice(&GAS_CONCENTRATION_RESPONSE[..4]);
+
+        let mut reader = PacketReader::new(&buffer);
+        let mut ppm = reader.ppm();
+        assert_eq!(Some(Ok(608)), ppm.next());
+        assert_eq!(Some(Err(MHZ19Error::WrongChecksum(0xD0, 0xD1))), ppm.next());
+        assert_eq!(Some(Ok(608)), ppm.next());
+        assert_eq!(None, ppm.next());
+        assert_eq!(36, reader.consumed());
+    }
+
+    #[test]
+    fn test_packet_filter() {
+        let calibration_ack = [0xFF, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
+        let mut corrupted = GAS_CONCENTRATION_RESPONSE;
+        corrupted[3] = 0x61;
+        let packets = [
+            calibration_ack,
+            GAS_CONCENTRATION_RESPONSE,
+            corrupted,
+            calibration_ack,
+        ];
+
+        let mut ppm = filter_gas_concentration(packets.iter().copied());
+        assert_eq!(Some(Ok(608)), ppm.next());
+        assert_eq!(Some(Err(MHZ19Error::WrongChecksum(0xD0, 0xD1))), ppm.next());
+        assert_eq!(None, ppm.next());
+
+        assert_eq!(
+            2,
+            filter_by_command(0x87, packets.iter().copied())
+                .filter(|result| *result == Ok(0))
+                .count()
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_multi_packet() {
+        use alloc::vec;
+
+        let mut corrupted = GAS_CONCENTRATION_RESPONSE;
+        corrupted[3] = 0x61;
+        let calibration_ack = [0xFF, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
+        let mut buffer = GAS_CONCENTRATION_RESPONSE.to_vec();
+        buffer.extend_from_slice(&corrupted);
+        buffer.extend_from_slice(&calibration_ack);
+        buffer.extend_from_slice(&GAS_CONCENTRATION_RESPONSE[..3]);
+
+        let reading = GasReading {
+            co2_ppm: 608,
+            temperature_celsius: 31,
+            status: 0,
+        };
+        assert_eq!(
+            (
+                vec![
+                    Ok(reading),
+                    Err(MHZ19Error::WrongChecksum(0xD0, 0xD1)),
+                    Err(MHZ19Error::WrongPacketType(0x86, 0x87)),
+                ],
+                27
+            ),
+            parse_multi_packet(&buffer)
+        );
+        assert_eq!((vec![], 0), parse_multi_packet(&[]));
+    }
+}
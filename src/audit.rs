@@ -0,0 +1,171 @@
+//! Audit trail of the calibrations sent to a sensor.
+//!
+//! Each [`LogEntry`] holds a checksum of its fields chained with the checksum of the previous
+//! entry, so modifying, inserting or removing an entry is detected by [`verify_log`]. The
+//! checksum (32 bits FNV-1a) is not cryptographic: it detects corruption and naive edits, not
+//! an attacker recomputing the chain.
+
+#[cfg(feature = "alloc")]
+use crate::{Command, CommandPacket};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// A calibration command sent to a sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogEntry<'a> {
+    /// Time the command was sent, in a unit chosen by the application
+    pub timestamp: u64,
+    /// Command byte (3rd byte) of the calibration command
+    pub command: u8,
+    pub operator_id: Option<&'a str>,
+    /// Was the calibration acknowledged by the sensor?
+    pub acknowledged: bool,
+    /// Checksum of the fields above and of the previous entry checksum
+    pub checksum: u32,
+}
+
+const FNV_OFFSET_BASIS: u32 = 0x811C_9DC5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+fn fnv1a(hash: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(FNV_PRIME)
+    })
+}
+
+impl<'a> LogEntry<'a> {
+    /// Create the entry following the one whose checksum is `previous_checksum` (`None` for the
+    /// first entry of a log).
+    pub fn new(
+        previous_checksum: Option<u32>,
+        timestamp: u64,
+        command: u8,
+        operator_id: Option<&'a str>,
+        acknowledged: bool,
+    ) -> Self {
+        let mut entry = LogEntry {
+            timestamp,
+            command,
+            operator_id,
+            acknowledged,
+            checksum: 0,
+        };
+        entry.checksum = entry.compute_checksum(previous_checksum);
+        entry
+    }
+
+    fn compute_checksum(&self, previous_checksum: Option<u32>) -> u32 {
+        let mut hash = fnv1a(
+            FNV_OFFSET_BASIS,
+            &previous_checksum.unwrap_or(0).to_le_bytes(),
+        );
+        hash = fnv1a(hash, &self.timestamp.to_le_bytes());
+        hash = fnv1a(hash, &[self.command, self.acknowledged as u8]);
+        match self.operator_id {
+            Some(operator_id) => {
+                // the length prefix keeps "ab" + "c" and "a" + "bc" apart
+                hash = fnv1a(hash, &[1]);
+                hash = fnv1a(hash, &(operator_id.len() as u32).to_le_bytes());
+                fnv1a(hash, operator_id.as_bytes())
+            }
+            None => fnv1a(hash, &[0]),
+        }
+    }
+
+    /// Is the checksum the one of this entry following the given one?
+    pub fn verify(&self, previous_checksum: Option<u32>) -> bool {
+        self.checksum == self.compute_checksum(previous_checksum)
+    }
+}
+
+/// Check the checksums of a whole log, starting from its first entry.
+pub fn verify_log(entries: &[LogEntry]) -> bool {
+    let mut previous_checksum = None;
+    entries.iter().all(|entry| {
+        let valid = entry.verify(previous_checksum);
+        previous_checksum = Some(entry.checksum);
+        valid
+    })
+}
+
+/// Log of the calibration commands sent to a sensor, see [`LogEntry`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionLog<'a> {
+    entries: Vec<LogEntry<'a>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> TransactionLog<'a> {
+    pub fn new() -> Self {
+        TransactionLog {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Record a command sent to the sensor: only zero and span calibrations are recorded.
+    pub fn record(
+        &mut self,
+        timestamp: u64,
+        command: &CommandPacket,
+        operator_id: Option<&'a str>,
+        acknowledged: bool,
+    ) {
+        let is_calibration =
+            Command::from_command_value(command[2]).is_some_and(|command| command.is_calibration());
+        if !is_calibration {
+            return;
+        }
+        let previous_checksum = self.entries.last().map(|entry| entry.checksum);
+        self.entries.push(LogEntry::new(
+            previous_checksum,
+            timestamp,
+            command[2],
+            operator_id,
+            acknowledged,
+        ));
+    }
+
+    pub fn entries(&self) -> &[LogEntry<'a>] {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_verify_log() {
+        let first = LogEntry::new(None, 1000, 0x87, Some("alice"), true);
+        let second = LogEntry::new(Some(first.checksum), 2000, 0x88, None, false);
+        let third = LogEntry::new(Some(second.checksum), 3000, 0x87, Some("bob"), true);
+        assert!(verify_log(&[first, second, third]));
+        assert!(verify_log(&[]));
+
+        let mut tampered = second;
+        tampered.acknowledged = true;
+        assert!(!verify_log(&[first, tampered, third]));
+        // removed entry
+        assert!(!verify_log(&[first, third]));
+        let mut renamed = first;
+        renamed.operator_id = Some("mallory");
+        assert!(!verify_log(&[renamed, second, third]));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_transaction_log() {
+        let mut log = TransactionLog::new();
+        log.record(1000, &crate::calibrate_zero_point(1), Some("alice"), true);
+        log.record(1500, &crate::read_gas_concentration(1), Some("alice"), true);
+        log.record(2000, &crate::calibrate_span_point(1, 2000), None, false);
+        assert_eq!(2, log.entries().len());
+        assert_eq!(
+            LogEntry::new(None, 1000, 0x87, Some("alice"), true),
+            log.entries()[0]
+        );
+        assert_eq!(0x88, log.entries()[1].command);
+        assert!(verify_log(log.entries()));
+    }
+}
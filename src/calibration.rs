@@ -0,0 +1,349 @@
+//! Guided span calibration procedure and records of the calibrations.
+
+use crate::{
+    calibrate_span_reference, calibrate_zero_reference, CommandPacket, SensorDuration,
+    SpanCalibrationPoint, SpanValue, ZeroCalibrationPoint,
+};
+
+/// Step of the span calibration procedure, see [`SpanCalibrationGuide`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationState {
+    /// The sensor must be placed in a gas of known concentration: 400ppm for the zero
+    /// calibration, then the span gas
+    CheckEnvironment {
+        required_ppm: u16,
+    },
+    /// The sensor readings are stabilizing in the calibration gas
+    Stabilising {
+        elapsed_s: u32,
+        required_s: u32,
+    },
+    /// The sensor is stable, the calibration (zero or span) can start
+    ReadyToCalibrate,
+    /// The zero calibration command has been sent
+    AwaitingZeroAck,
+    /// The span calibration command has been sent
+    AwaitingSpanAck,
+    Complete,
+}
+
+/// Events advancing a [`SpanCalibrationGuide`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationEvent {
+    /// The user confirmed the sensor is in the required gas
+    EnvironmentConfirmed,
+    /// Some seconds elapsed
+    TimeElapsed(u32),
+    /// The user asked to start the calibration
+    Start,
+    /// The last command sent has been processed by the sensor.
+    ///
+    /// Whether the sensor answers calibration commands depends on its firmware: the caller
+    /// decides when a command is acknowledged, eg once the answer was checked with
+    /// [`parse_calibration_result`](crate::parse_calibration_result), or once the command was
+    /// written to the uart when the firmware sends none.
+    Acknowledged,
+}
+
+/// What the caller must do after an event, see [`SpanCalibrationGuide::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationAction {
+    /// Nothing to do but waiting for the next event
+    Wait,
+    /// Send this command to the sensor
+    SendCommand(CommandPacket),
+    /// The calibration is over
+    Done,
+}
+
+/// State machine of the span calibration procedure of the datasheet, in two phases: the sensor
+/// is placed in 400ppm air, left for 20 minutes to stabilize and calibrated at its zero point,
+/// then placed in the span gas, left for 20 minutes again and calibrated at its span point.
+///
+/// Events not expected in the current state are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanCalibrationGuide {
+    device_number: u8,
+    zero: ZeroCalibrationPoint,
+    span: SpanCalibrationPoint,
+    required_s: u32,
+    zero_calibrated: bool,
+    state: CalibrationState,
+}
+
+impl SpanCalibrationGuide {
+    /// Stabilization time required by the datasheet
+    pub const STABILIZATION_SECONDS: u32 =
+        SensorDuration::CALIBRATION_STABILISATION.0.as_secs() as u32;
+
+    /// Guide the calibration of the given sensor at the `zero` then `span` reference
+    /// concentrations.
+    pub fn new(device_number: u8, zero: ZeroCalibrationPoint, span: SpanCalibrationPoint) -> Self {
+        SpanCalibrationGuide {
+            device_number,
+            zero,
+            span,
+            required_s: Self::STABILIZATION_SECONDS,
+            zero_calibrated: false,
+            state: CalibrationState::CheckEnvironment {
+                required_ppm: zero.reference_ppm,
+            },
+        }
+    }
+
+    pub fn state(&self) -> CalibrationState {
+        self.state
+    }
+
+    pub fn step(&mut self, event: CalibrationEvent) -> CalibrationAction {
+        use CalibrationEvent::*;
+        use CalibrationState::*;
+        let (state, action) = match (self.state, event) {
+            (CheckEnvironment { .. }, EnvironmentConfirmed) => (
+                Stabilising {
+                    elapsed_s: 0,
+                    required_s: self.required_s,
+                },
+                CalibrationAction::Wait,
+            ),
+            (
+                Stabilising {
+                    elapsed_s,
+                    required_s,
+                },
+                TimeElapsed(seconds),
+            ) => {
+                let elapsed_s = elapsed_s.saturating_add(seconds);
+                if elapsed_s >= required_s {
+                    (ReadyToCalibrate, CalibrationAction::Wait)
+                } else {
+                    (
+                        Stabilising {
+                            elapsed_s,
+                            required_s,
+                        },
+                        CalibrationAction::Wait,
+                    )
+                }
+            }
+            (ReadyToCalibrate, Start) if !self.zero_calibrated => (
+                AwaitingZeroAck,
+                CalibrationAction::SendCommand(calibrate_zero_reference(
+                    self.device_number,
+                    self.zero,
+                )),
+            ),
+            (AwaitingZeroAck, Acknowledged) => {
+                self.zero_calibrated = true;
+                (
+                    CheckEnvironment {
+                        required_ppm: self.span.reference_ppm.ppm(),
+                    },
+                    CalibrationAction::Wait,
+                )
+            }
+            (ReadyToCalibrate, Start) => (
+                AwaitingSpanAck,
+                CalibrationAction::SendCommand(calibrate_span_reference(
+                    self.device_number,
+                    self.span,
+                )),
+            ),
+            (AwaitingSpanAck, Acknowledged) | (Complete, _) => (Complete, CalibrationAction::Done),
+            (state, _) => (state, CalibrationAction::Wait),
+        };
+        self.state = state;
+        action
+    }
+}
+
+/// Parameters of the last calibrations of a sensor, to store them in an EEPROM or flash memory
+/// and check later that the sensor did not drift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorCalibrationRecord {
+    pub zero_ppm: u16,
+    pub span_ppm: u16,
+    /// Time of the zero calibration, in a unit chosen by the application, 0 if never done
+    pub zero_timestamp: u64,
+    /// Time of the span calibration, in a unit chosen by the application, 0 if never done
+    pub span_timestamp: u64,
+    /// Identifier of the operator, eg an UTF-8 name padded with 0x00 bytes
+    pub calibrated_by: [u8; 16],
+}
+
+impl SensorCalibrationRecord {
+    /// Factory calibration assumed for a fresh sensor: 400ppm zero point, 2000ppm span, never
+    /// calibrated in the field (and thus not valid, see [`Self::is_valid`]).
+    pub const DEFAULT: SensorCalibrationRecord = SensorCalibrationRecord {
+        zero_ppm: ZeroCalibrationPoint::STANDARD.reference_ppm,
+        span_ppm: 2000,
+        zero_timestamp: 0,
+        span_timestamp: 0,
+        calibrated_by: [0; 16],
+    };
+
+    /// Length of the record serialized by [`Self::to_bytes`]
+    pub const SERIALIZED_LEN: usize = 36;
+
+    /// Is the record the one of a field calibration within the sensor specifications?
+    ///
+    /// Both calibrations must have been done (non zero timestamps), the zero point must be
+    /// between 380 and 420ppm and the span between 1000 and 10000ppm.
+    pub fn is_valid(&self) -> bool {
+        self.zero_ppm < self.span_ppm
+            && self.zero_timestamp != 0
+            && self.span_timestamp != 0
+            && (380..=420).contains(&self.zero_ppm)
+            && SpanValue::new(self.span_ppm).is_some()
+            && self.span_ppm <= 10000
+    }
+
+    /// Serialize the record, big endian like the sensor protocol: zero ppm, span ppm, zero
+    /// timestamp, span timestamp then the operator identifier.
+    pub fn to_bytes(&self) -> [u8; Self::SERIALIZED_LEN] {
+        let mut bytes = [0; Self::SERIALIZED_LEN];
+        bytes[0..2].copy_from_slice(&self.zero_ppm.to_be_bytes());
+        bytes[2..4].copy_from_slice(&self.span_ppm.to_be_bytes());
+        bytes[4..12].copy_from_slice(&self.zero_timestamp.to_be_bytes());
+        bytes[12..20].copy_from_slice(&self.span_timestamp.to_be_bytes());
+        bytes[20..].copy_from_slice(&self.calibrated_by);
+        bytes
+    }
+
+    /// Deserialize a record written by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; Self::SERIALIZED_LEN]) -> Self {
+        let u64_at = |start: usize| {
+            let mut u64_bytes = [0; 8];
+            u64_bytes.copy_from_slice(&bytes[start..start + 8]);
+            u64::from_be_bytes(u64_bytes)
+        };
+        let mut calibrated_by = [0; 16];
+        calibrated_by.copy_from_slice(&bytes[20..]);
+        SensorCalibrationRecord {
+            zero_ppm: u16::from_be_bytes([bytes[0], bytes[1]]),
+            span_ppm: u16::from_be_bytes([bytes[2], bytes[3]]),
+            zero_timestamp: u64_at(4),
+            span_timestamp: u64_at(12),
+            calibrated_by,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_span_calibration_guide() {
+        let mut guide = SpanCalibrationGuide::new(
+            1,
+            ZeroCalibrationPoint::STANDARD,
+            SpanCalibrationPoint::RECOMMENDED,
+        );
+        // zero phase, in outdoor air
+        assert_eq!(
+            CalibrationState::CheckEnvironment { required_ppm: 400 },
+            guide.state()
+        );
+        // calibration cannot start before the sensor is stable
+        assert_eq!(CalibrationAction::Wait, guide.step(CalibrationEvent::Start));
+        assert_eq!(
+            CalibrationAction::Wait,
+            guide.step(CalibrationEvent::EnvironmentConfirmed)
+        );
+        assert_eq!(
+            CalibrationAction::Wait,
+            guide.step(CalibrationEvent::TimeElapsed(600))
+        );
+        assert_eq!(CalibrationAction::Wait, guide.step(CalibrationEvent::Start));
+        assert_eq!(
+            CalibrationState::Stabilising {
+                elapsed_s: 600,
+                required_s: 1200
+            },
+            guide.state()
+        );
+        assert_eq!(
+            CalibrationAction::Wait,
+            guide.step(CalibrationEvent::TimeElapsed(600))
+        );
+        assert_eq!(CalibrationState::ReadyToCalibrate, guide.state());
+        assert_eq!(
+            CalibrationAction::SendCommand(crate::calibrate_zero_point(1)),
+            guide.step(CalibrationEvent::Start)
+        );
+        assert_eq!(CalibrationState::AwaitingZeroAck, guide.state());
+
+        // span phase: the sensor must be moved to the span gas and stabilize again
+        assert_eq!(
+            CalibrationAction::Wait,
+            guide.step(CalibrationEvent::Acknowledged)
+        );
+        assert_eq!(
+            CalibrationState::CheckEnvironment { required_ppm: 2000 },
+            guide.state()
+        );
+        assert_eq!(CalibrationAction::Wait, guide.step(CalibrationEvent::Start));
+        assert_eq!(
+            CalibrationAction::Wait,
+            guide.step(CalibrationEvent::EnvironmentConfirmed)
+        );
+        assert_eq!(
+            CalibrationAction::Wait,
+            guide.step(CalibrationEvent::TimeElapsed(1200))
+        );
+        assert_eq!(CalibrationState::ReadyToCalibrate, guide.state());
+        assert_eq!(
+            CalibrationAction::SendCommand(crate::calibrate_span_point(1, 2000)),
+            guide.step(CalibrationEvent::Start)
+        );
+        assert_eq!(CalibrationState::AwaitingSpanAck, guide.state());
+        assert_eq!(
+            CalibrationAction::Done,
+            guide.step(CalibrationEvent::Acknowledged)
+        );
+        assert_eq!(CalibrationState::Complete, guide.state());
+        assert_eq!(CalibrationAction::Done, guide.step(CalibrationEvent::Start));
+    }
+
+    #[test]
+    fn test_sensor_calibration_record() {
+        assert!(!SensorCalibrationRecord::DEFAULT.is_valid());
+        let mut calibrated_by = [0; 16];
+        calibrated_by[..5].copy_from_slice(b"alice");
+        let record = SensorCalibrationRecord {
+            zero_ppm: 410,
+            span_ppm: 2000,
+            zero_timestamp: 1_700_000_000,
+            span_timestamp: 1_700_001_200,
+            calibrated_by,
+        };
+        assert!(record.is_valid());
+        assert_eq!(
+            record,
+            SensorCalibrationRecord::from_bytes(&record.to_bytes())
+        );
+        assert_eq!([0x01, 0x9A, 0x07, 0xD0], record.to_bytes()[..4]);
+
+        for invalid in [
+            SensorCalibrationRecord {
+                zero_ppm: 500,
+                ..record
+            },
+            SensorCalibrationRecord {
+                span_ppm: 900,
+                ..record
+            },
+            SensorCalibrationRecord {
+                span_ppm: 20000,
+                ..record
+            },
+            SensorCalibrationRecord {
+                span_timestamp: 0,
+                ..record
+            },
+        ] {
+            assert!(!invalid.is_valid());
+        }
+    }
+}
@@ -0,0 +1,1346 @@
+//! Blocking driver on top of a `std::io` serial port (requires the `std` feature).
+//!
+//! The serial port must be configured by the caller (9600 bauds, 8 data bits, 1 stop bit,
+//! no parity) with a read timeout.
+
+use crate::assembler::PacketAssembler;
+use crate::stats::{Ema, Ring};
+use crate::{
+    parse_abc_status, parse_detection_range, parse_firmware_version, parse_gas_concentration_ppm,
+    parse_gas_reading, read_abc_status, read_detection_range, read_firmware_version,
+    read_gas_concentration, Command, CommandPacket, DetectionRange, FirmwareVersion, GasReading,
+    MHZ19Error, Packet, ResponsePacket, SensorDuration,
+};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+/// Counters of the packets exchanged with the sensor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Number of valid packets received
+    pub frames: u32,
+    /// Number of packets rejected because of a wrong checksum
+    pub checksum_failures: u32,
+    /// Number of reads that timed out
+    pub timeouts: u32,
+    /// Number of times bytes were discarded to find the next start byte
+    pub resyncs: u32,
+    /// Number of packets made only of 0xFF or only of 0x00 bytes, read while no sensor answers
+    pub no_sensor: u32,
+}
+
+#[derive(Debug)]
+pub enum DriverError {
+    /// Error of the underlying serial port
+    Io(io::Error),
+    /// No complete packet was received before the serial port read timeout
+    Timeout,
+    /// The received packet is invalid
+    Protocol(MHZ19Error),
+    /// A response was received while no command was waiting for one
+    UnexpectedResponse,
+    /// A calibration command was refused: the previous one is too recent, see
+    /// `DriverConfig::calibration_cooldown`. Holds the remaining cooldown time.
+    CalibrationCooldown(Duration),
+}
+
+impl std::error::Error for DriverError {}
+
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use DriverError::*;
+        match self {
+            Io(e) => write!(f, "Serial port error: {}", e),
+            Timeout => write!(f, "Timeout while waiting for the sensor response"),
+            Protocol(e) => write!(f, "Invalid response: {}", e),
+            UnexpectedResponse => write!(f, "Response received without a pending command"),
+            CalibrationCooldown(remaining) => write!(
+                f,
+                "Calibration refused, the previous one is too recent (retry in {:?})",
+                remaining
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for DriverError {
+    fn from(e: io::Error) -> Self {
+        DriverError::Io(e)
+    }
+}
+
+impl From<MHZ19Error> for DriverError {
+    fn from(e: MHZ19Error) -> Self {
+        DriverError::Protocol(e)
+    }
+}
+
+/// Read bytes until a valid packet is received.
+///
+/// Bytes before a start byte and packets with a wrong checksum are skipped. A packet made only
+/// of 0xFF or only of 0x00 bytes (no sensor answering) is reported as a `NoSensorResponse`
+/// error. Reaching the end of the reader is reported as an `UnexpectedEof` io error.
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Packet, DriverError> {
+    let mut assembler = PacketAssembler::new();
+    let mut byte = [0u8];
+    loop {
+        reader.read_exact(&mut byte)?;
+        match assembler.push(byte[0]) {
+            Some(Ok(packet)) => return Ok(packet),
+            Some(Err(MHZ19Error::NoSensorResponse)) => {
+                return Err(DriverError::Protocol(MHZ19Error::NoSensorResponse))
+            }
+            Some(Err(_)) | None => {}
+        }
+    }
+}
+
+/// Request/response exchanges with a sensor.
+pub trait Driver {
+    /// Device number of the sensor the commands are addressed to
+    fn device_number(&self) -> u8;
+
+    /// Send a command packet and wait for the sensor response.
+    fn transact(&mut self, command: &CommandPacket) -> Result<ResponsePacket, DriverError>;
+}
+
+/// Byte transport to a sensor, the [`Mhz19Driver`] logic being written once against it.
+///
+/// Implemented for every `std::io` serial port (`Read + Write`): a new backend only needs to
+/// implement these two methods.
+pub trait Transport {
+    /// Read the next byte sent by the sensor, `DriverError::Timeout` if none was received in time.
+    fn read_byte(&mut self) -> Result<u8, DriverError>;
+
+    /// Write all the bytes of a packet, flushing them to the sensor.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), DriverError>;
+}
+
+/// A read returning no bytes, a `TimedOut` or a `WouldBlock` error is reported as
+/// `DriverError::Timeout`.
+impl<T: Read + Write> Transport for T {
+    fn read_byte(&mut self) -> Result<u8, DriverError> {
+        let mut byte = [0u8];
+        loop {
+            match self.read(&mut byte) {
+                Ok(0) => return Err(DriverError::Timeout),
+                Ok(_) => return Ok(byte[0]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e)
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    return Err(DriverError::Timeout)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), DriverError> {
+        self.write_all(bytes)?;
+        self.flush()?;
+        Ok(())
+    }
+}
+
+/// Optional behaviors of a [`Mhz19Driver`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DriverConfig {
+    /// Weight of each new reading in the exponential moving average computed by
+    /// `read_co2_smoothed` (between 0 excluded and 1), no smoothing if `None`
+    pub ema_alpha: Option<f32>,
+    /// Minimum delay between two commands, enforced by waiting before sending a command
+    pub min_command_interval: Option<Duration>,
+    /// Minimum delay between two zero or span calibrations, enforced by refusing to send a
+    /// calibration command: repeated calibrations can harm the sensor
+    pub calibration_cooldown: Option<Duration>,
+}
+
+/// Source of time of a [`Mhz19Driver`], replaceable in tests.
+pub trait Clock {
+    /// Time elapsed since an arbitrary origin
+    fn now(&self) -> Duration;
+
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// Sleep until `interval` elapsed since `last`, if any.
+fn wait_since(clock: &mut impl Clock, last: Option<Duration>, interval: Duration) {
+    if let Some(last) = last {
+        let elapsed = clock.now().saturating_sub(last);
+        if elapsed < interval {
+            clock.sleep(interval - elapsed);
+        }
+    }
+}
+
+/// The system monotonic clock.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// Blocking driver of a sensor connected to a serial port, or any other [`Transport`].
+///
+/// The driver is `Clone` when its port and clock are, eg to checkpoint its state (assembler,
+/// moving average, counters) before an experiment. Both copies then write to the same
+/// underlying port, if the port clone shares it.
+#[derive(Clone)]
+pub struct Mhz19Driver<S, C = SystemClock> {
+    port: S,
+    device_number: u8,
+    assembler: PacketAssembler,
+    timeouts: u32,
+    ema: Option<Ema>,
+    clock: C,
+    min_command_interval: Option<Duration>,
+    last_command: Option<Duration>,
+    calibration_cooldown: Option<Duration>,
+    last_calibration: Option<Duration>,
+}
+
+impl<S: Transport> Mhz19Driver<S> {
+    pub fn new(port: S, device_number: u8) -> Self {
+        Self::with_config(port, device_number, DriverConfig::default())
+    }
+
+    pub fn with_config(port: S, device_number: u8, config: DriverConfig) -> Self {
+        Mhz19Driver::with_clock(port, device_number, config, SystemClock::default())
+    }
+}
+
+impl<S: Transport, C: Clock> Mhz19Driver<S, C> {
+    pub fn with_clock(port: S, device_number: u8, config: DriverConfig, clock: C) -> Self {
+        Mhz19Driver {
+            port,
+            device_number,
+            assembler: PacketAssembler::new(),
+            timeouts: 0,
+            ema: config.ema_alpha.map(Ema::new),
+            clock,
+            min_command_interval: config.min_command_interval,
+            last_command: None,
+            calibration_cooldown: config.calibration_cooldown,
+            last_calibration: None,
+        }
+    }
+
+    /// Send a command packet to the sensor, first waiting for `DriverConfig::min_command_interval`
+    /// to elapse since the previous command.
+    ///
+    /// Calibration commands sent before the end of `DriverConfig::calibration_cooldown` are
+    /// refused with `DriverError::CalibrationCooldown`.
+    pub fn send(&mut self, command: &CommandPacket) -> Result<(), DriverError> {
+        let calibration =
+            Command::from_command_value(command[2]).is_some_and(|command| command.is_calibration());
+        if calibration {
+            if let (Some(cooldown), Some(last_calibration)) =
+                (self.calibration_cooldown, self.last_calibration)
+            {
+                let elapsed = self.clock.now().saturating_sub(last_calibration);
+                if elapsed < cooldown {
+                    return Err(DriverError::CalibrationCooldown(cooldown - elapsed));
+                }
+            }
+        }
+        if let Some(interval) = self.min_command_interval {
+            wait_since(&mut self.clock, self.last_command, interval);
+        }
+        let result = self.port.write_bytes(command.as_ref());
+        self.last_command = Some(self.clock.now());
+        if calibration && result.is_ok() {
+            self.last_calibration = self.last_command;
+        }
+        result
+    }
+
+    /// Wait for the next packet sent by the sensor.
+    pub fn receive(&mut self) -> Result<ResponsePacket, DriverError> {
+        loop {
+            match self.port.read_byte() {
+                Ok(byte) => {
+                    if let Some(result) = self.assembler.push(byte) {
+                        return Ok(result?.into());
+                    }
+                }
+                Err(DriverError::Timeout) => {
+                    self.timeouts += 1;
+                    return Err(DriverError::Timeout);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Query the CO2 gas concentration in ppm.
+    pub fn read_co2(&mut self) -> Result<u32, DriverError> {
+        self.send(&read_gas_concentration(self.device_number))?;
+        Ok(parse_gas_concentration_ppm(&self.receive()?)?)
+    }
+
+    /// Query the CO2 gas concentration in ppm, making up to `attempts` queries (at least one)
+    /// while the response is corrupted.
+    ///
+    /// Before each new query, the stale bytes sent by the sensor are drained until the serial
+    /// port read times out, so the next response is read from its start byte. Other errors,
+    /// including timeouts, are returned immediately.
+    pub fn read_co2_with_resync(&mut self, attempts: u8) -> Result<u32, DriverError> {
+        let mut attempt = 1;
+        loop {
+            match self.read_co2() {
+                Err(DriverError::Protocol(
+                    MHZ19Error::WrongChecksum(_, _)
+                    | MHZ19Error::WrongStartByte(_)
+                    | MHZ19Error::WrongPacketLength(_)
+                    | MHZ19Error::NoSensorResponse,
+                )) if attempt < attempts => {
+                    self.drain()?;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Discard the bytes received until the read times out.
+    fn drain(&mut self) -> Result<(), DriverError> {
+        loop {
+            match self.port.read_byte() {
+                Ok(_) => {}
+                Err(DriverError::Timeout) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        self.assembler.clear();
+        Ok(())
+    }
+
+    /// Query the CO2 gas concentration in ppm, smoothed by the exponential moving average
+    /// configured by `DriverConfig::ema_alpha`.
+    ///
+    /// Without `ema_alpha`, the reading is returned as is.
+    pub fn read_co2_smoothed(&mut self) -> Result<f32, DriverError> {
+        let ppm = self.read_co2()?;
+        Ok(match &mut self.ema {
+            Some(ema) => ema.update(ppm),
+            None => ppm as f32,
+        })
+    }
+
+    /// Query the firmware version, detection range, ABC status and gas concentration, for
+    /// commissioning.
+    ///
+    /// Unlike [`collect_diagnostics`], every query must succeed: the first failure is returned.
+    pub fn self_check(&mut self) -> Result<SelfCheckReport, DriverError> {
+        let device_number = self.device_number;
+        let firmware_version =
+            parse_firmware_version(&self.transact(&read_firmware_version(device_number))?)?;
+        let detection_range_ppm =
+            parse_detection_range(&self.transact(&read_detection_range(device_number))?)?;
+        let abc_enabled = parse_abc_status(&self.transact(&read_abc_status(device_number))?)?;
+        let reading = parse_gas_reading(&self.transact(&read_gas_concentration(device_number))?)?;
+        Ok(SelfCheckReport {
+            firmware_version,
+            detection_range_ppm,
+            abc_enabled,
+            reading,
+        })
+    }
+
+    pub fn link_stats(&self) -> LinkStats {
+        let stats = self.assembler.stats();
+        LinkStats {
+            frames: stats.frames,
+            checksum_failures: stats.checksum_failures,
+            timeouts: self.timeouts,
+            resyncs: stats.resyncs,
+            no_sensor: stats.no_sensor,
+        }
+    }
+
+    pub fn reset_link_stats(&mut self) {
+        self.assembler.reset_stats();
+        self.timeouts = 0;
+    }
+
+    /// Get back the serial port.
+    pub fn into_inner(self) -> S {
+        self.port
+    }
+}
+
+impl<S: Transport, C: Clock> Driver for Mhz19Driver<S, C> {
+    fn device_number(&self) -> u8 {
+        self.device_number
+    }
+
+    fn transact(&mut self, command: &CommandPacket) -> Result<ResponsePacket, DriverError> {
+        self.send(command)?;
+        self.receive()
+    }
+}
+
+/// The last `N` settings commands sent to a sensor (ABC, detection range), to send them again
+/// when the sensor was power cycled or replaced.
+///
+/// Other commands (reads, calibrations) are not recorded, neither are consecutive duplicates.
+#[derive(Debug, Clone)]
+pub struct CommandHistory<const N: usize> {
+    commands: Ring<CommandPacket, N>,
+}
+
+impl<const N: usize> Default for CommandHistory<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> CommandHistory<N> {
+    pub fn new() -> Self {
+        CommandHistory {
+            commands: Ring::new(CommandPacket([0; 9])),
+        }
+    }
+
+    /// Record a sent command, dropping the oldest one if the history is full.
+    pub fn record(&mut self, command: &CommandPacket) {
+        let is_setting = Command::from_command_value(command[2]).is_some_and(|c| c.is_setting());
+        if is_setting && self.iter().last() != Some(command) {
+            self.commands.push(*command);
+        }
+    }
+
+    /// Recorded commands, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &CommandPacket> + '_ {
+        self.commands.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Write the recorded commands again, oldest first.
+    pub fn replay_into(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for command in self.iter() {
+            writer.write_all(command.as_ref())?;
+        }
+        writer.flush()
+    }
+}
+
+/// Configuration and reading of a sensor, see [`Mhz19Driver::self_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfCheckReport {
+    pub firmware_version: FirmwareVersion,
+    /// Detection range in ppm, see [`DetectionRange::from_ppm`]
+    pub detection_range_ppm: u16,
+    pub abc_enabled: bool,
+    pub reading: GasReading,
+}
+
+/// Diagnostic information of a sensor, see [`collect_diagnostics`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorDiagnostics {
+    pub co2_ppm: u32,
+    pub temperature_celsius: i16,
+    /// `None` if the sensor did not answer the query
+    pub abc_enabled: Option<bool>,
+    /// `None` if the sensor did not answer the query or reported an unknown range
+    pub detection_range: Option<DetectionRange>,
+    /// `None` if the sensor did not answer the query
+    pub firmware_version: Option<FirmwareVersion>,
+    /// Ratio of the queries that failed, between 0 and 1
+    pub packet_loss_rate: f32,
+}
+
+/// Query all the diagnostic information of a sensor.
+///
+/// The ABC status, detection range and firmware version queries are not supported by every
+/// firmware: their failures are reported as `None`. Only a failure of the gas concentration
+/// query is returned as an error.
+pub fn collect_diagnostics(driver: &mut impl Driver) -> Result<SensorDiagnostics, DriverError> {
+    let device_number = driver.device_number();
+    let reading = parse_gas_reading(&driver.transact(&read_gas_concentration(device_number))?)?;
+
+    let mut failures = 0u8;
+    let abc_enabled = query(
+        driver,
+        &read_abc_status(device_number),
+        parse_abc_status,
+        &mut failures,
+    );
+    let detection_range = query(
+        driver,
+        &read_detection_range(device_number),
+        parse_detection_range,
+        &mut failures,
+    )
+    .and_then(DetectionRange::from_ppm);
+    let firmware_version = query(
+        driver,
+        &read_firmware_version(device_number),
+        parse_firmware_version,
+        &mut failures,
+    );
+
+    Ok(SensorDiagnostics {
+        co2_ppm: reading.co2_ppm,
+        temperature_celsius: reading.temperature_celsius,
+        abc_enabled,
+        detection_range,
+        firmware_version,
+        packet_loss_rate: failures as f32 / 4.0,
+    })
+}
+
+/// Send an optional query, counting the failures.
+fn query<T>(
+    driver: &mut impl Driver,
+    command: &CommandPacket,
+    parse: fn(&[u8]) -> Result<T, MHZ19Error>,
+    failures: &mut u8,
+) -> Option<T> {
+    let value = driver
+        .transact(command)
+        .ok()
+        .and_then(|response| parse(&response).ok());
+    if value.is_none() {
+        *failures += 1;
+    }
+    value
+}
+
+/// Is a sensor answering to the given device number?
+///
+/// Sends up to `attempts` "read gas concentration" commands, until a valid response is received.
+pub fn detect_sensor(driver: &mut impl Driver, device_number: u8, attempts: u8) -> bool {
+    (0..attempts).any(|_| {
+        driver
+            .transact(&read_gas_concentration(device_number))
+            .is_ok_and(|response| parse_gas_reading(&response).is_ok())
+    })
+}
+
+/// Probe all the device numbers (1 to 255) in sequence, yielding the ones a sensor answers to.
+///
+/// Responses do not contain the device number: on a bus of sensors ignoring the device number
+/// of the commands, every device number is reported.
+pub fn detect_all_sensors(driver: &mut impl Driver) -> impl Iterator<Item = u8> + '_ {
+    (1..=255).filter(move |device_number| detect_sensor(driver, *device_number, 1))
+}
+
+/// Switch of the sensor power supply, eg a GPIO driving a transistor.
+///
+/// Implement it for the output pin type of the platform, eg by calling `set_high` / `set_low`
+/// of an `embedded_hal::digital::OutputPin`.
+pub trait PowerSwitch {
+    fn power_on(&mut self) -> Result<(), DriverError>;
+
+    fn power_off(&mut self) -> Result<(), DriverError>;
+}
+
+/// A [`Mhz19Driver`] whose sensor power is controlled by a [`PowerSwitch`], to power cycle the
+/// sensor to recover from errors or to save energy.
+pub struct PoweredSensor<S, P, C = SystemClock> {
+    driver: Mhz19Driver<S, C>,
+    switch: P,
+}
+
+impl<S: Transport, P: PowerSwitch, C: Clock> PoweredSensor<S, P, C> {
+    /// Time the sensor is kept off by [`Self::power_cycle`]
+    pub const POWER_OFF_DURATION: Duration = Duration::from_secs(1);
+
+    pub fn new(driver: Mhz19Driver<S, C>, switch: P) -> Self {
+        PoweredSensor { driver, switch }
+    }
+
+    /// Power the sensor on. Its readings are not reliable before it warmed up, see
+    /// [`SensorDuration::WARMUP`].
+    pub fn power_on(&mut self) -> Result<(), DriverError> {
+        self.switch.power_on()?;
+        // bytes received before the power off are not part of the next response
+        self.driver.assembler.clear();
+        Ok(())
+    }
+
+    pub fn power_off(&mut self) -> Result<(), DriverError> {
+        self.switch.power_off()
+    }
+
+    /// Power the sensor off for [`Self::POWER_OFF_DURATION`], then on, blocking for the given
+    /// warmup duration.
+    pub fn power_cycle(&mut self, warmup: SensorDuration) -> Result<(), DriverError> {
+        self.power_off()?;
+        self.driver.clock.sleep(Self::POWER_OFF_DURATION);
+        self.power_on()?;
+        self.driver.clock.sleep(warmup.0);
+        Ok(())
+    }
+
+    pub fn driver(&mut self) -> &mut Mhz19Driver<S, C> {
+        &mut self.driver
+    }
+
+    /// Get back the driver and the power switch.
+    pub fn into_inner(self) -> (Mhz19Driver<S, C>, P) {
+        (self.driver, self.switch)
+    }
+}
+
+/// Round-robin polling of the gas concentration of several sensors sharing a bus.
+///
+/// Commands are spaced by at least `interval` so the bus is not flooded, and each response is
+/// attributed to the sensor of the last command: the responses do not contain the device number.
+#[derive(Debug, Clone)]
+pub struct RoundRobinPoller<const N: usize, C = SystemClock> {
+    sensors: [u8; N],
+    current: usize,
+    interval: Duration,
+    clock: C,
+    last_command: Option<Duration>,
+    pending: Option<u8>,
+}
+
+impl<const N: usize> RoundRobinPoller<N> {
+    /// Poll the sensors of the given device numbers, in order.
+    pub fn new(sensors: [u8; N], interval: Duration) -> Self {
+        Self::with_clock(sensors, interval, SystemClock::default())
+    }
+}
+
+impl<const N: usize, C: Clock> RoundRobinPoller<N, C> {
+    /// Fails to compile when there is no sensor to poll (`N` is 0)
+    const AT_LEAST_ONE_SENSOR: () = assert!(N > 0, "RoundRobinPoller needs at least one sensor");
+
+    /// Poll the sensors of the given device numbers in order, using the given clock to space
+    /// the commands.
+    pub fn with_clock(sensors: [u8; N], interval: Duration, clock: C) -> Self {
+        let () = Self::AT_LEAST_ONE_SENSOR;
+        RoundRobinPoller {
+            sensors,
+            current: 0,
+            interval,
+            clock,
+            last_command: None,
+            pending: None,
+        }
+    }
+
+    /// Next command to send, with the device number of its sensor.
+    ///
+    /// Waits for `interval` to elapse since the previous command. The response must then be
+    /// given to [`Self::process_response`].
+    pub fn next_command(&mut self) -> (u8, CommandPacket) {
+        wait_since(&mut self.clock, self.last_command, self.interval);
+        let device_number = self.sensors[self.current];
+        self.current = (self.current + 1) % N;
+        self.last_command = Some(self.clock.now());
+        self.pending = Some(device_number);
+        (device_number, read_gas_concentration(device_number))
+    }
+
+    /// Parse the response to the last command, returning the device number of its sensor.
+    pub fn process_response(&mut self, packet: &[u8]) -> Result<(u8, GasReading), DriverError> {
+        let device_number = self.pending.take().ok_or(DriverError::UnexpectedResponse)?;
+        Ok((device_number, parse_gas_reading(packet)?))
+    }
+}
+
+/// Recovery action to take when a sensor keeps failing, see [`apply_reset_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorResetPolicy {
+    /// Keep retrying without resetting the sensor
+    NoReset,
+    /// Cut the sensor power for the given time; the power switch is driven by the application
+    PowerCycle { off_duration_ms: u32 },
+    /// Send a command resetting the sensor, eg a settings command known to restart it
+    SoftReset { command: CommandPacket },
+    /// Stop querying the sensor and report the failure
+    ReportAndGiveUp,
+}
+
+/// Action taken by [`apply_reset_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetAction {
+    None,
+    /// The application must switch the sensor power off, then on after `off_duration_ms`
+    PowerCycleRequired {
+        off_duration_ms: u32,
+    },
+    /// The reset command was written to the sensor, whatever its answer
+    SoftResetSent,
+    /// The reset command could not be sent: serial port error or command refused by the driver
+    SoftResetFailed,
+    GaveUp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetResult {
+    pub action_taken: ResetAction,
+    /// Time to wait before querying the sensor again, 0 to retry immediately (`NoReset`) or
+    /// never (`ReportAndGiveUp`)
+    pub retry_after_ms: u32,
+}
+
+/// Apply a recovery policy to a sensor which stopped responding.
+///
+/// After a power cycle the sensor must warm up again (3 minutes) before being queried. A soft
+/// reset command is usually not answered: a timeout or an invalid packet received while waiting
+/// for its response is not a failure.
+pub fn apply_reset_policy(policy: &SensorResetPolicy, driver: &mut impl Driver) -> ResetResult {
+    let duration_ms = |duration: SensorDuration| duration.0.as_millis() as u32;
+    let (action_taken, retry_after_ms) = match *policy {
+        SensorResetPolicy::NoReset => (ResetAction::None, 0),
+        SensorResetPolicy::PowerCycle { off_duration_ms } => (
+            ResetAction::PowerCycleRequired { off_duration_ms },
+            off_duration_ms.saturating_add(duration_ms(SensorDuration::WARMUP)),
+        ),
+        SensorResetPolicy::SoftReset { command } => match driver.transact(&command) {
+            Ok(_)
+            | Err(DriverError::Timeout)
+            | Err(DriverError::Protocol(_))
+            | Err(DriverError::UnexpectedResponse) => (
+                ResetAction::SoftResetSent,
+                duration_ms(SensorDuration::COMMAND_RESPONSE_TIMEOUT),
+            ),
+            Err(DriverError::Io(_)) | Err(DriverError::CalibrationCooldown(_)) => (
+                ResetAction::SoftResetFailed,
+                duration_ms(SensorDuration::COMMAND_RESPONSE_TIMEOUT),
+            ),
+        },
+        SensorResetPolicy::ReportAndGiveUp => (ResetAction::GaveUp, 0),
+    };
+    ResetResult {
+        action_taken,
+        retry_after_ms,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Serial port replaying canned bytes, a `None` chunk simulating a read timeout.
+    #[derive(Default)]
+    pub(crate) struct MockPort {
+        pub(crate) input: VecDeque<Option<Vec<u8>>>,
+        pub(crate) written: Vec<u8>,
+    }
+
+    impl MockPort {
+        pub(crate) fn new(input: Vec<Option<Vec<u8>>>) -> Self {
+            MockPort {
+                input: input.into(),
+                written: Vec::new(),
+            }
+        }
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.input.pop_front() {
+                Some(Some(mut chunk)) => {
+                    let len = chunk.len().min(buf.len());
+                    buf[..len].copy_from_slice(&chunk[..len]);
+                    if len < chunk.len() {
+                        self.input.push_front(Some(chunk.split_off(len)));
+                    }
+                    Ok(len)
+                }
+                Some(None) | None => Err(io::ErrorKind::TimedOut.into()),
+            }
+        }
+    }
+
+    impl Write for MockPort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    pub(crate) static GAS_CONCENTRATION_RESPONSE: Packet =
+        [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+
+    #[test]
+    fn test_read_frame() {
+        let mut corrupted = GAS_CONCENTRATION_RESPONSE;
+        corrupted[3] = 0x61;
+        let mut bytes = vec![0x12, 0x34];
+        bytes.extend_from_slice(&corrupted);
+        bytes.push(0x56);
+        bytes.extend_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        bytes.extend_from_slice(&[0xFF, 0x86]);
+        let mut reader = io::Cursor::new(bytes);
+
+        assert_eq!(GAS_CONCENTRATION_RESPONSE, read_frame(&mut reader).unwrap());
+        match read_frame(&mut reader) {
+            Err(DriverError::Io(e)) => assert_eq!(io::ErrorKind::UnexpectedEof, e.kind()),
+            r => panic!("Unexpected result {:?}", r),
+        }
+
+        let mut bytes = vec![0xFF; 9];
+        bytes.extend_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        let mut reader = io::Cursor::new(bytes);
+        assert!(matches!(
+            read_frame(&mut reader),
+            Err(DriverError::Protocol(MHZ19Error::NoSensorResponse))
+        ));
+        assert_eq!(GAS_CONCENTRATION_RESPONSE, read_frame(&mut reader).unwrap());
+    }
+
+    #[test]
+    fn test_read_co2() {
+        let port = MockPort::new(vec![Some(GAS_CONCENTRATION_RESPONSE.to_vec())]);
+        let mut driver = Mhz19Driver::new(port, 1);
+        assert_eq!(608, driver.read_co2().unwrap());
+        assert_eq!(
+            read_gas_concentration(1).to_vec(),
+            driver.into_inner().written
+        );
+    }
+
+    #[test]
+    fn test_collect_diagnostics() {
+        let port = MockPort::new(vec![
+            Some(GAS_CONCENTRATION_RESPONSE.to_vec()),
+            // no answer to the ABC status query
+            None,
+            Some(vec![0xFF, 0x9B, 0x00, 0x00, 0x07, 0xD0, 0x00, 0x00, 0x8E]),
+            Some(vec![0xFF, 0xA0, 0x30, 0x34, 0x34, 0x33, 0x00, 0x00, 0x95]),
+        ]);
+        let mut driver = Mhz19Driver::new(port, 1);
+        assert_eq!(
+            SensorDiagnostics {
+                co2_ppm: 608,
+                temperature_celsius: 31,
+                abc_enabled: None,
+                detection_range: Some(DetectionRange::Ppm2000),
+                firmware_version: Some(FirmwareVersion {
+                    major: 4,
+                    minor: 43,
+                    patch: 0,
+                }),
+                packet_loss_rate: 0.25,
+            },
+            collect_diagnostics(&mut driver).unwrap()
+        );
+
+        let port = MockPort::new(vec![None]);
+        let mut driver = Mhz19Driver::new(port, 1);
+        assert!(matches!(
+            collect_diagnostics(&mut driver),
+            Err(DriverError::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_self_check() {
+        let mut abc_status = vec![0xFF, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00];
+        abc_status[8] = crate::checksum(&abc_status[1..8]);
+        let responses = vec![
+            Some(vec![0xFF, 0xA0, 0x30, 0x34, 0x34, 0x33, 0x00, 0x00, 0x95]),
+            Some(vec![0xFF, 0x9B, 0x00, 0x00, 0x07, 0xD0, 0x00, 0x00, 0x8E]),
+            Some(abc_status),
+            Some(GAS_CONCENTRATION_RESPONSE.to_vec()),
+        ];
+        let mut driver = Mhz19Driver::new(MockPort::new(responses.clone()), 1);
+        assert_eq!(
+            SelfCheckReport {
+                firmware_version: FirmwareVersion {
+                    major: 4,
+                    minor: 43,
+                    patch: 0,
+                },
+                detection_range_ppm: 2000,
+                abc_enabled: true,
+                reading: GasReading {
+                    co2_ppm: 608,
+                    temperature_celsius: 31,
+                    status: 0
+                }
+            },
+            driver.self_check().unwrap()
+        );
+
+        let mut responses = responses;
+        responses[2] = None;
+        let mut driver = Mhz19Driver::new(MockPort::new(responses), 1);
+        assert!(matches!(driver.self_check(), Err(DriverError::Timeout)));
+    }
+
+    #[test]
+    fn test_detect_sensor() {
+        let port = MockPort::new(vec![None, Some(GAS_CONCENTRATION_RESPONSE.to_vec())]);
+        let mut driver = Mhz19Driver::new(port, 1);
+        assert!(detect_sensor(&mut driver, 3, 2));
+        assert_eq!(
+            [
+                read_gas_concentration(3).to_vec(),
+                read_gas_concentration(3).to_vec()
+            ]
+            .concat(),
+            driver.into_inner().written
+        );
+
+        let port = MockPort::new(vec![None, Some(GAS_CONCENTRATION_RESPONSE.to_vec())]);
+        let mut driver = Mhz19Driver::new(port, 1);
+        assert!(!detect_sensor(&mut driver, 1, 1));
+        assert!(!detect_sensor(&mut driver, 1, 0));
+    }
+
+    /// Power switch recording its states
+    #[derive(Default)]
+    struct MockSwitch {
+        states: Vec<bool>,
+    }
+
+    impl PowerSwitch for MockSwitch {
+        fn power_on(&mut self) -> Result<(), DriverError> {
+            self.states.push(true);
+            Ok(())
+        }
+
+        fn power_off(&mut self) -> Result<(), DriverError> {
+            self.states.push(false);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_powered_sensor() {
+        let port = MockPort::new(vec![Some(GAS_CONCENTRATION_RESPONSE[..4].to_vec()), None]);
+        let driver =
+            Mhz19Driver::with_clock(port, 1, DriverConfig::default(), MockClock::default());
+        let mut sensor = PoweredSensor::new(driver, MockSwitch::default());
+        // the sensor stops answering in the middle of a response
+        assert!(sensor.driver().read_co2().is_err());
+        assert_eq!(4, sensor.driver().assembler.buffered_len());
+
+        sensor.power_cycle(SensorDuration::WARMUP).unwrap();
+        assert_eq!(0, sensor.driver().assembler.buffered_len());
+        sensor.power_off().unwrap();
+        let (driver, switch) = sensor.into_inner();
+        assert_eq!(vec![false, true, false], switch.states);
+        assert_eq!(
+            vec![Duration::from_secs(1), Duration::from_secs(180)],
+            driver.clock.sleeps
+        );
+    }
+
+    #[test]
+    fn test_round_robin_poller() {
+        let mut poller = RoundRobinPoller::with_clock(
+            [1, 2, 3],
+            Duration::from_millis(100),
+            MockClock::default(),
+        );
+        assert!(matches!(
+            poller.process_response(&GAS_CONCENTRATION_RESPONSE),
+            Err(DriverError::UnexpectedResponse)
+        ));
+        for device_number in [1, 2, 3, 1] {
+            assert_eq!(
+                (device_number, read_gas_concentration(device_number)),
+                poller.next_command()
+            );
+            let (responding, reading) = poller
+                .process_response(&GAS_CONCENTRATION_RESPONSE)
+                .unwrap();
+            assert_eq!(device_number, responding);
+            assert_eq!(608, reading.co2_ppm);
+            poller.clock.now += Duration::from_millis(40);
+        }
+        assert_eq!(vec![Duration::from_millis(60); 3], poller.clock.sleeps);
+
+        poller.next_command();
+        assert!(matches!(
+            poller.process_response(&[0xFF; 9]),
+            Err(DriverError::Protocol(MHZ19Error::NoSensorResponse))
+        ));
+    }
+
+    #[test]
+    fn test_apply_reset_policy() {
+        let mut driver = Mhz19Driver::new(MockPort::new(vec![None]), 1);
+        let command = crate::set_detection_range(1, 5000);
+        assert_eq!(
+            ResetResult {
+                action_taken: ResetAction::SoftResetSent,
+                retry_after_ms: 1000
+            },
+            apply_reset_policy(&SensorResetPolicy::SoftReset { command }, &mut driver)
+        );
+        assert_eq!(
+            ResetResult {
+                action_taken: ResetAction::PowerCycleRequired {
+                    off_duration_ms: 500
+                },
+                retry_after_ms: 180_500
+            },
+            apply_reset_policy(
+                &SensorResetPolicy::PowerCycle {
+                    off_duration_ms: 500
+                },
+                &mut driver
+            )
+        );
+        assert_eq!(
+            ResetAction::None,
+            apply_reset_policy(&SensorResetPolicy::NoReset, &mut driver).action_taken
+        );
+        assert_eq!(
+            ResetAction::GaveUp,
+            apply_reset_policy(&SensorResetPolicy::ReportAndGiveUp, &mut driver).action_taken
+        );
+        // only the soft reset writes to the sensor
+        assert_eq!(command.to_vec(), driver.into_inner().written);
+
+        // a garbled answer means the command reached the sensor
+        let garbled = vec![0xFF, 0x99, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+        let mut driver = Mhz19Driver::new(MockPort::new(vec![Some(garbled)]), 1);
+        assert_eq!(
+            ResetAction::SoftResetSent,
+            apply_reset_policy(&SensorResetPolicy::SoftReset { command }, &mut driver).action_taken
+        );
+    }
+
+    #[test]
+    fn test_detect_all_sensors() {
+        let mut input = vec![None; 255];
+        input[1] = Some(GAS_CONCENTRATION_RESPONSE.to_vec());
+        input[41] = Some(GAS_CONCENTRATION_RESPONSE.to_vec());
+        let mut driver = Mhz19Driver::new(MockPort::new(input), 1);
+        assert_eq!(
+            vec![2, 42],
+            detect_all_sensors(&mut driver).collect::<Vec<_>>()
+        );
+        assert_eq!(255 * 9, driver.into_inner().written.len());
+    }
+
+    #[test]
+    fn test_command_history() {
+        let mut history = CommandHistory::<2>::new();
+        assert!(history.is_empty());
+        for _ in 0..100 {
+            history.record(&read_gas_concentration(1));
+        }
+        history.record(&crate::set_detection_range(1, 2000));
+        history.record(&crate::calibrate_zero_point(1));
+        history.record(&crate::set_detection_range(1, 2000));
+        assert_eq!(1, history.len());
+        let mut port = MockPort::default();
+        history.replay_into(&mut port).unwrap();
+        assert_eq!(crate::set_detection_range(1, 2000).to_vec(), port.written);
+
+        history.record(&crate::set_automatic_baseline_correction(1, false));
+        history.record(&crate::set_detection_range(1, 5000));
+        assert_eq!(
+            vec![
+                &crate::set_automatic_baseline_correction(1, false),
+                &crate::set_detection_range(1, 5000)
+            ],
+            history.iter().collect::<Vec<_>>()
+        );
+        let mut port = MockPort::default();
+        history.replay_into(&mut port).unwrap();
+        assert_eq!(
+            [
+                crate::set_automatic_baseline_correction(1, false).to_vec(),
+                crate::set_detection_range(1, 5000).to_vec()
+            ]
+            .concat(),
+            port.written
+        );
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+
+    /// Transport replaying canned bytes, a `None` simulating a timeout.
+    #[derive(Clone)]
+    struct MockTransport {
+        input: VecDeque<Option<u8>>,
+        written: Vec<u8>,
+    }
+
+    impl Transport for MockTransport {
+        fn read_byte(&mut self) -> Result<u8, DriverError> {
+            self.input.pop_front().flatten().ok_or(DriverError::Timeout)
+        }
+
+        fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), DriverError> {
+            self.written.extend_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_mock_transport() {
+        let mut input: VecDeque<_> = GAS_CONCENTRATION_RESPONSE
+            .iter()
+            .copied()
+            .map(Some)
+            .collect();
+        input.push_back(None);
+        let transport = MockTransport {
+            input,
+            written: Vec::new(),
+        };
+        let mut driver = Mhz19Driver::new(transport, 2);
+        assert_eq!(608, driver.read_co2().unwrap());
+        assert!(matches!(driver.read_co2(), Err(DriverError::Timeout)));
+        assert_eq!(1, driver.link_stats().timeouts);
+        assert_eq!(
+            [
+                read_gas_concentration(2).to_vec(),
+                read_gas_concentration(2).to_vec()
+            ]
+            .concat(),
+            driver.into_inner().written
+        );
+    }
+
+    #[test]
+    fn test_clone() {
+        let input = [
+            gas_concentration_response(400),
+            gas_concentration_response(800),
+        ]
+        .concat()
+        .into_iter()
+        .map(Some)
+        .collect();
+        let transport = MockTransport {
+            input,
+            written: Vec::new(),
+        };
+        let config = DriverConfig {
+            ema_alpha: Some(0.5),
+            ..DriverConfig::default()
+        };
+        let mut driver = Mhz19Driver::with_config(transport, 1, config);
+        assert_eq!(400.0, driver.read_co2_smoothed().unwrap());
+
+        let mut checkpoint = driver.clone();
+        assert_eq!(600.0, driver.read_co2_smoothed().unwrap());
+        assert!(driver.read_co2_smoothed().is_err());
+        // the checkpoint has its own copy of the state and of the mock input
+        assert_eq!(600.0, checkpoint.read_co2_smoothed().unwrap());
+        assert_eq!(0, checkpoint.link_stats().timeouts);
+        assert_eq!(1, driver.link_stats().timeouts);
+    }
+
+    #[test]
+    fn test_read_co2_with_resync() {
+        let mut corrupted = GAS_CONCENTRATION_RESPONSE;
+        corrupted[3] = 0x61;
+        let input = corrupted
+            .iter()
+            .chain(&[0x12, 0xFF, 0x86])
+            .copied()
+            .map(Some)
+            .chain(Some(None))
+            .chain(GAS_CONCENTRATION_RESPONSE.iter().copied().map(Some))
+            .collect();
+        let transport = MockTransport {
+            input,
+            written: Vec::new(),
+        };
+        let mut driver = Mhz19Driver::new(transport.clone(), 1);
+        assert_eq!(608, driver.read_co2_with_resync(2).unwrap());
+        // the drain timeout is not a link failure
+        assert_eq!(0, driver.link_stats().timeouts);
+        assert_eq!(
+            [
+                read_gas_concentration(1).to_vec(),
+                read_gas_concentration(1).to_vec()
+            ]
+            .concat(),
+            driver.into_inner().written
+        );
+
+        let mut driver = Mhz19Driver::new(transport, 1);
+        assert!(matches!(
+            driver.read_co2_with_resync(1),
+            Err(DriverError::Protocol(MHZ19Error::WrongChecksum(0xD0, 0xD1)))
+        ));
+    }
+
+    /// Build a valid "read gas concentration" response
+    pub(crate) fn gas_concentration_response(ppm: u16) -> Vec<u8> {
+        crate::build_gas_concentration_response(ppm as u32, 31, 0).to_vec()
+    }
+
+    #[test]
+    fn test_read_co2_smoothed() {
+        let responses = vec![
+            Some(gas_concentration_response(400)),
+            Some(gas_concentration_response(800)),
+            None,
+            Some(gas_concentration_response(400)),
+        ];
+        let config = DriverConfig {
+            ema_alpha: Some(0.25),
+            ..DriverConfig::default()
+        };
+        let mut driver = Mhz19Driver::with_config(MockPort::new(responses.clone()), 1, config);
+        assert_eq!(400.0, driver.read_co2_smoothed().unwrap());
+        assert_eq!(500.0, driver.read_co2_smoothed().unwrap());
+        // failures do not alter the average
+        assert!(driver.read_co2_smoothed().is_err());
+        assert_eq!(475.0, driver.read_co2_smoothed().unwrap());
+
+        let mut driver = Mhz19Driver::new(MockPort::new(responses), 1);
+        assert_eq!(400.0, driver.read_co2_smoothed().unwrap());
+        assert_eq!(800.0, driver.read_co2_smoothed().unwrap());
+    }
+
+    /// Clock advancing only when sleeping or explicitly told to
+    #[derive(Default)]
+    pub(crate) struct MockClock {
+        pub(crate) now: Duration,
+        pub(crate) sleeps: Vec<Duration>,
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Duration {
+            self.now
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.sleeps.push(duration);
+            self.now += duration;
+        }
+    }
+
+    #[test]
+    fn test_min_command_interval() {
+        let config = DriverConfig {
+            min_command_interval: Some(Duration::from_millis(100)),
+            ..DriverConfig::default()
+        };
+        let port = MockPort::default();
+        let mut driver = Mhz19Driver::with_clock(port, 1, config, MockClock::default());
+        driver.send(&read_gas_concentration(1)).unwrap();
+        driver.clock.now += Duration::from_millis(30);
+        driver.send(&read_gas_concentration(1)).unwrap();
+        driver.clock.now += Duration::from_millis(150);
+        driver.send(&read_gas_concentration(1)).unwrap();
+        assert_eq!(vec![Duration::from_millis(70)], driver.clock.sleeps);
+        assert_eq!(27, driver.into_inner().written.len());
+
+        let mut driver = Mhz19Driver::with_clock(
+            MockPort::default(),
+            1,
+            DriverConfig::default(),
+            MockClock::default(),
+        );
+        driver.send(&read_gas_concentration(1)).unwrap();
+        driver.send(&read_gas_concentration(1)).unwrap();
+        assert!(driver.clock.sleeps.is_empty());
+    }
+
+    #[test]
+    fn test_calibration_cooldown() {
+        let config = DriverConfig {
+            calibration_cooldown: Some(Duration::from_secs(600)),
+            ..DriverConfig::default()
+        };
+        let mut driver =
+            Mhz19Driver::with_clock(MockPort::default(), 1, config, MockClock::default());
+        driver.send(&crate::calibrate_zero_point(1)).unwrap();
+        driver.clock.now += Duration::from_secs(60);
+        assert!(matches!(
+            driver.send(&crate::calibrate_span_point(1, 2000)),
+            Err(DriverError::CalibrationCooldown(remaining)) if remaining == Duration::from_secs(540)
+        ));
+        // other commands are not affected
+        driver.send(&read_gas_concentration(1)).unwrap();
+        driver.clock.now += Duration::from_secs(540);
+        driver.send(&crate::calibrate_zero_point(1)).unwrap();
+        assert_eq!(
+            [
+                crate::calibrate_zero_point(1).to_vec(),
+                read_gas_concentration(1).to_vec(),
+                crate::calibrate_zero_point(1).to_vec()
+            ]
+            .concat(),
+            driver.into_inner().written
+        );
+    }
+
+    #[test]
+    fn test_link_stats() {
+        let mut corrupted = GAS_CONCENTRATION_RESPONSE;
+        corrupted[3] = 0x61;
+        let port = MockPort::new(vec![
+            Some(vec![0x00, 0x01]),
+            Some(corrupted.to_vec()),
+            None,
+            Some(GAS_CONCENTRATION_RESPONSE.to_vec()),
+            Some(vec![0xFF; 18]),
+        ]);
+        let mut driver = Mhz19Driver::new(port, 1);
+
+        assert!(matches!(
+            driver.read_co2(),
+            Err(DriverError::Protocol(MHZ19Error::WrongChecksum(0xD0, 0xD1)))
+        ));
+        assert!(matches!(driver.read_co2(), Err(DriverError::Timeout)));
+        assert_eq!(608, driver.read_co2().unwrap());
+        // unplugged sensor
+        for _ in 0..2 {
+            assert!(matches!(
+                driver.read_co2(),
+                Err(DriverError::Protocol(MHZ19Error::NoSensorResponse))
+            ));
+        }
+        assert_eq!(
+            LinkStats {
+                frames: 1,
+                checksum_failures: 1,
+                timeouts: 1,
+                resyncs: 2,
+                no_sensor: 2,
+            },
+            driver.link_stats()
+        );
+
+        driver.reset_link_stats();
+        assert_eq!(LinkStats::default(), driver.link_stats());
+    }
+}
@@ -0,0 +1,140 @@
+//! Formatting helpers usable without `std`.
+
+use crate::GasReading;
+use core::fmt::{self, Write};
+
+/// Format bytes as uppercase hexadecimal, separated by spaces: `FF 86 02 60`.
+///
+/// Works in `no_std` contexts, eg `write!(uart, "{}", HexSlice(&packet[..]))`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct HexSlice<'a>(pub &'a [u8]);
+
+impl<'a> fmt::Display for HexSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Debug for HexSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Write a reading as a JSON object in the given buffer, returning the number of bytes written:
+/// `{"co2_ppm":412,"temperature_celsius":24,"status":0}`.
+///
+/// Fails if the buffer is too small, 64 bytes are always enough.
+pub fn write_measurement_json(
+    reading: &GasReading,
+    buffer: &mut [u8],
+) -> Result<usize, fmt::Error> {
+    let mut writer = SliceWriter::new(buffer);
+    write!(
+        writer,
+        "{{\"co2_ppm\":{},\"temperature_celsius\":{},\"status\":{}}}",
+        reading.co2_ppm, reading.temperature_celsius, reading.status
+    )?;
+    Ok(writer.written().len())
+}
+
+/// `fmt::Write` implementation filling a byte slice, failing once the slice is full.
+pub(crate) struct SliceWriter<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SliceWriter<'a> {
+    pub(crate) fn new(buffer: &'a mut [u8]) -> Self {
+        SliceWriter { buffer, len: 0 }
+    }
+
+    /// Bytes written so far
+    pub(crate) fn written(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// Give back the written part of the buffer.
+    pub(crate) fn into_slice(self) -> &'a [u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// Give back the written part of the buffer as a string.
+    pub(crate) fn into_str(self) -> &'a str {
+        let written = &self.buffer[..self.len];
+        // only `str`s are written
+        core::str::from_utf8(written).unwrap()
+    }
+}
+
+impl<'a> fmt::Write for SliceWriter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buffer.len() {
+            return Err(fmt::Error);
+        }
+        self.buffer[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hex_slice() {
+        let mut buffer = [0u8; 32];
+        let mut writer = SliceWriter::new(&mut buffer);
+        write!(writer, "{}", HexSlice(&[0xFF, 0x86, 0x02, 0x0A])).unwrap();
+        assert_eq!("FF 86 02 0A", writer.into_str());
+
+        let mut writer = SliceWriter::new(&mut buffer);
+        write!(writer, "{:?}|{}", HexSlice(&[0x01]), HexSlice(&[])).unwrap();
+        assert_eq!("01|", writer.into_str());
+
+        let mut writer = SliceWriter::new(&mut buffer);
+        write!(writer, "{}", crate::read_gas_concentration(1)).unwrap();
+        assert_eq!("FF 01 86 00 00 00 00 00 79", writer.into_str());
+    }
+
+    #[test]
+    fn test_slice_writer_overflow() {
+        let mut buffer = [0u8; 4];
+        let mut writer = SliceWriter::new(&mut buffer);
+        assert_eq!(Err(fmt::Error), write!(writer, "{}", HexSlice(&[1, 2])));
+        assert!(writer.written().starts_with(b"01 "));
+    }
+
+    #[test]
+    fn test_write_measurement_json() {
+        let mut buffer = [0u8; 64];
+        let reading = GasReading {
+            co2_ppm: 412,
+            temperature_celsius: 24,
+            status: 0,
+        };
+        let len = write_measurement_json(&reading, &mut buffer).unwrap();
+        assert_eq!(
+            &b"{\"co2_ppm\":412,\"temperature_celsius\":24,\"status\":0}"[..],
+            &buffer[..len]
+        );
+        let reading = GasReading {
+            co2_ppm: u32::MAX,
+            temperature_celsius: i16::MIN,
+            status: u8::MAX,
+        };
+        assert!(write_measurement_json(&reading, &mut buffer).is_ok());
+        assert_eq!(
+            Err(fmt::Error),
+            write_measurement_json(&reading, &mut buffer[..20])
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! Indoor Air Quality (IAQ) score approximated from the CO2 concentration.
+//!
+//! The IAQ score (0 to 500, as reported by the Bosch BME680 sensor) combines several gases: the
+//! approximation from CO2 alone only helps displaying the readings alongside the scores of other
+//! sensors.
+
+/// (ppm, IAQ score) points of the piecewise linear approximation
+const IAQ_POINTS: [(u32, u32); 5] = [(0, 0), (400, 25), (1000, 100), (2000, 250), (5000, 500)];
+
+/// Approximate the IAQ score of a concentration.
+///
+/// The score is interpolated between 0 ppm: 0, 400 ppm: 25, 1000 ppm: 100, 2000 ppm: 250 and
+/// 5000 ppm: 500, and is 500 above 5000 ppm.
+pub fn co2_ppm_to_iaq_score(ppm: u32) -> u16 {
+    let (max_ppm, max_score) = IAQ_POINTS[IAQ_POINTS.len() - 1];
+    if ppm >= max_ppm {
+        return max_score as u16;
+    }
+    let upper = IAQ_POINTS
+        .iter()
+        .position(|(point_ppm, _)| ppm < *point_ppm)
+        .unwrap_or(IAQ_POINTS.len() - 1);
+    let (low_ppm, low_score) = IAQ_POINTS[upper - 1];
+    let (high_ppm, high_score) = IAQ_POINTS[upper];
+    (low_score + (ppm - low_ppm) * (high_score - low_score) / (high_ppm - low_ppm)) as u16
+}
+
+/// Category of an IAQ score, following the BME680 ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IaqCategory {
+    /// 0 to 50
+    Excellent,
+    /// 51 to 100
+    Good,
+    /// 101 to 150
+    Moderate,
+    /// 151 to 200
+    Poor,
+    /// 201 to 300
+    VeryPoor,
+    /// Above 300
+    Hazardous,
+}
+
+impl IaqCategory {
+    pub fn from_score(score: u16) -> IaqCategory {
+        match score {
+            0..=50 => IaqCategory::Excellent,
+            51..=100 => IaqCategory::Good,
+            101..=150 => IaqCategory::Moderate,
+            151..=200 => IaqCategory::Poor,
+            201..=300 => IaqCategory::VeryPoor,
+            _ => IaqCategory::Hazardous,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_co2_ppm_to_iaq_score() {
+        assert_eq!(0, co2_ppm_to_iaq_score(0));
+        assert_eq!(25, co2_ppm_to_iaq_score(400));
+        assert_eq!(100, co2_ppm_to_iaq_score(1000));
+        assert_eq!(175, co2_ppm_to_iaq_score(1500));
+        assert_eq!(250, co2_ppm_to_iaq_score(2000));
+        assert_eq!(500, co2_ppm_to_iaq_score(5000));
+        assert_eq!(500, co2_ppm_to_iaq_score(u32::MAX));
+        // the score never decreases with the concentration
+        for ppm in 1..=6000 {
+            assert!(co2_ppm_to_iaq_score(ppm) >= co2_ppm_to_iaq_score(ppm - 1));
+        }
+    }
+
+    #[test]
+    fn test_iaq_category() {
+        assert_eq!(IaqCategory::Excellent, IaqCategory::from_score(25));
+        assert_eq!(IaqCategory::Good, IaqCategory::from_score(100));
+        assert_eq!(IaqCategory::Moderate, IaqCategory::from_score(101));
+        assert_eq!(IaqCategory::Poor, IaqCategory::from_score(175));
+        assert_eq!(IaqCategory::VeryPoor, IaqCategory::from_score(250));
+        assert_eq!(IaqCategory::Hazardous, IaqCategory::from_score(500));
+        assert_eq!(
+            IaqCategory::Good,
+            IaqCategory::from_score(co2_ppm_to_iaq_score(800))
+        );
+    }
+}
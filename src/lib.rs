@@ -10,15 +10,48 @@
 //! - functions for parsing response read from the uart
 //! - functions to create command payload to send to the sensor though the uart.
 //!
+//! The `alloc` feature enables the functions returning heap allocated values, for `no_std`
+//! targets with a global allocator. It is enabled by the `std` feature. Without any feature, the
+//! crate is `no_std`; see the Readme for the supported feature combinations.
+//!
+//! Other modules provide:
+//! - [`assembler`]: reassembly of packets from a stream of bytes,
+//! - [`audit`]: hash-chained log of the calibrations,
+//! - [`calibration`]: guided span calibration procedure, calibration records,
+//! - `driver`: a blocking driver on top of a `std::io` serial port (`std` feature),
+//! - [`fmt`]: formatting without allocation (hexadecimal bytes, JSON readings),
+//! - [`iaq`]: Indoor Air Quality score approximated from the concentration,
+//! - [`logger`]: buffering of timestamped readings flushed as CSV,
+//! - [`nmea`]: NMEA 0183 style sentences,
+//! - [`session`]: decoding of captured command / response logs,
+//! - [`stats`]: statistics over series of readings,
+//! - `units`: physical models built on the readings (`units` feature).
+//!
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-#[cfg(not(feature = "std"))]
-use core::fmt;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::convert::TryFrom;
+use core::ops::Deref;
+
+pub mod assembler;
+pub mod audit;
+pub mod calibration;
 #[cfg(feature = "std")]
-use std::fmt;
+pub mod driver;
+pub mod fmt;
+pub mod iaq;
+pub mod logger;
+pub mod nmea;
+pub mod session;
+pub mod stats;
+#[cfg(feature = "units")]
+pub mod units;
 
 /// MH-Z12 Commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Command {
     /// Read the gas concentration
     ReadGasConcentration,
@@ -30,8 +63,21 @@ enum Command {
     CalibrateSpan,
     /// Enable or disable Automatic Baseline Correction (MH-Z19B only)
     SetAutomaticBaselineCorrection,
+    /// Enable or disable Automatic Baseline Correction, persisted across power cycles
+    /// (undocumented, some MH-Z19B firmwares only)
+    SetPersistentAutomaticBaselineCorrection,
     /// Set the sensor range detection (2000 or 5000 MH-Z19B only)
     SetSensorDetectionRange,
+    /// Read the Automatic Baseline Correction status (undocumented)
+    ReadAutomaticBaselineCorrection,
+    /// Read the sensor range detection (undocumented)
+    ReadSensorDetectionRange,
+    /// Read the firmware version (undocumented)
+    ReadFirmwareVersion,
+    /// Write a word of the user EEPROM (undocumented, some MH-Z14A derivatives only)
+    WriteEeprom,
+    /// Read a word of the user EEPROM (undocumented, some MH-Z14A derivatives only)
+    ReadEeprom,
 }
 
 impl Command {
@@ -42,38 +88,286 @@ impl Command {
             CalibrateZero => 0x87,
             CalibrateSpan => 0x88,
             SetAutomaticBaselineCorrection => 0x79,
+            SetPersistentAutomaticBaselineCorrection => 0x78,
             SetSensorDetectionRange => 0x99,
+            ReadAutomaticBaselineCorrection => 0x7D,
+            ReadSensorDetectionRange => 0x9B,
+            ReadFirmwareVersion => 0xA0,
+            WriteEeprom => 0xC4,
+            ReadEeprom => 0xC5,
+        }
+    }
+
+    fn get_name(&self) -> &'static str {
+        use Command::*;
+        match self {
+            ReadGasConcentration => "ReadGasConcentration",
+            CalibrateZero => "CalibrateZero",
+            CalibrateSpan => "CalibrateSpan",
+            SetAutomaticBaselineCorrection => "SetAutomaticBaselineCorrection",
+            SetPersistentAutomaticBaselineCorrection => "SetPersistentAutomaticBaselineCorrection",
+            SetSensorDetectionRange => "SetSensorDetectionRange",
+            ReadAutomaticBaselineCorrection => "ReadAutomaticBaselineCorrection",
+            ReadSensorDetectionRange => "ReadSensorDetectionRange",
+            ReadFirmwareVersion => "ReadFirmwareVersion",
+            WriteEeprom => "WriteEeprom",
+            ReadEeprom => "ReadEeprom",
+        }
+    }
+
+    /// All the known commands
+    const ALL: [Command; 11] = [
+        Command::ReadGasConcentration,
+        Command::CalibrateZero,
+        Command::CalibrateSpan,
+        Command::SetAutomaticBaselineCorrection,
+        Command::SetPersistentAutomaticBaselineCorrection,
+        Command::SetSensorDetectionRange,
+        Command::ReadAutomaticBaselineCorrection,
+        Command::ReadSensorDetectionRange,
+        Command::ReadFirmwareVersion,
+        Command::WriteEeprom,
+        Command::ReadEeprom,
+    ];
+
+    fn from_command_value(command_byte: u8) -> Option<Command> {
+        Command::ALL
+            .iter()
+            .copied()
+            .find(|command| command.get_command_value() == command_byte)
+    }
+
+    /// Does the command change a setting of the sensor, lost when the sensor is replaced?
+    #[cfg(feature = "std")]
+    fn is_setting(&self) -> bool {
+        use Command::*;
+        match self {
+            SetAutomaticBaselineCorrection
+            | SetPersistentAutomaticBaselineCorrection
+            | SetSensorDetectionRange => true,
+            ReadGasConcentration
+            | CalibrateZero
+            | CalibrateSpan
+            | ReadAutomaticBaselineCorrection
+            | ReadSensorDetectionRange
+            | ReadFirmwareVersion
+            | WriteEeprom
+            | ReadEeprom => false,
+        }
+    }
+
+    /// Is the command a zero or span calibration?
+    #[cfg(feature = "alloc")]
+    fn is_calibration(&self) -> bool {
+        matches!(self, Command::CalibrateZero | Command::CalibrateSpan)
+    }
+
+    /// Positions of the response bytes that are expected to be 0x00
+    fn get_reserved_response_bytes(&self) -> &'static [usize] {
+        use Command::*;
+        match self {
+            // 4: temperature, 5: status
+            ReadGasConcentration => &[6, 7],
+            // 7: ABC status
+            ReadAutomaticBaselineCorrection => &[5, 6],
+            // 4-5: detection range
+            ReadSensorDetectionRange => &[6, 7],
+            // 2-5: version
+            ReadFirmwareVersion => &[6, 7],
+            // 2: address, 3-4: data
+            WriteEeprom | ReadEeprom => &[5, 6, 7],
+            CalibrateZero
+            | CalibrateSpan
+            | SetAutomaticBaselineCorrection
+            | SetPersistentAutomaticBaselineCorrection
+            | SetSensorDetectionRange => &[5, 6, 7],
         }
     }
 }
 
+/// Get a human readable name of a command byte (eg `"ReadGasConcentration"` for 0x86).
+///
+/// Returns `"Unknown"` for command bytes not known by this crate.
+pub fn packet_type_name(command_byte: u8) -> &'static str {
+    Command::from_command_value(command_byte)
+        .map(|command| command.get_name())
+        .unwrap_or("Unknown")
+}
+
 /// Both input and output packets are 9 bytes long
 pub type Packet = [u8; 9];
 
+/// A packet created by the command builders, to be sent to the sensor.
+///
+/// Unlike [`ResponsePacket`], it does not dereference to a `[u8]` slice: it cannot be given
+/// to a response parser by mistake. Use `as_ref()` to get the bytes to write to the uart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandPacket(Packet);
+
+impl Deref for CommandPacket {
+    type Target = Packet;
+
+    fn deref(&self) -> &Packet {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for CommandPacket {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Packet> for CommandPacket {
+    fn from(packet: Packet) -> Self {
+        CommandPacket(packet)
+    }
+}
+
+impl From<CommandPacket> for Packet {
+    fn from(packet: CommandPacket) -> Self {
+        packet.0
+    }
+}
+
+impl PartialEq<Packet> for CommandPacket {
+    fn eq(&self, other: &Packet) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<CommandPacket> for Packet {
+    fn eq(&self, other: &CommandPacket) -> bool {
+        *self == other.0
+    }
+}
+
+/// Formatted as hexadecimal bytes, eg `FF 01 86 00 00 00 00 00 79`
+impl core::fmt::Display for CommandPacket {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Display::fmt(&fmt::HexSlice(&self.0), f)
+    }
+}
+
+/// A packet received from the sensor.
+///
+/// It dereferences to a `[u8]` slice so it can be given to the response parsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponsePacket(Packet);
+
+impl Deref for ResponsePacket {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for ResponsePacket {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Packet> for ResponsePacket {
+    fn from(packet: Packet) -> Self {
+        ResponsePacket(packet)
+    }
+}
+
+impl From<ResponsePacket> for Packet {
+    fn from(packet: ResponsePacket) -> Self {
+        packet.0
+    }
+}
+
+/// Formatted as hexadecimal bytes, eg `FF 86 02 60 47 00 00 00 D1`
+impl core::fmt::Display for ResponsePacket {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Display::fmt(&fmt::HexSlice(&self.0), f)
+    }
+}
+
+impl TryFrom<&[u8]> for ResponsePacket {
+    type Error = MHZ19Error;
+
+    /// Copy the bytes of a response, failing if there are not exactly 9 bytes
+    fn try_from(bytes: &[u8]) -> Result<Self, MHZ19Error> {
+        let mut packet = [0u8; 9];
+        if bytes.len() != packet.len() {
+            return Err(MHZ19Error::WrongPacketLength(bytes.len()));
+        }
+        packet.copy_from_slice(bytes);
+        Ok(ResponsePacket(packet))
+    }
+}
+
+/// Note that the standard library already provides `Packet::try_from(Vec<u8>)` (giving the
+/// vector back on error) and `Vec::from(Packet)`.
+#[cfg(feature = "alloc")]
+impl TryFrom<alloc::vec::Vec<u8>> for ResponsePacket {
+    type Error = MHZ19Error;
+
+    /// Take the bytes of a response, failing if there are not exactly 9 bytes
+    fn try_from(bytes: alloc::vec::Vec<u8>) -> Result<Self, MHZ19Error> {
+        ResponsePacket::try_from(bytes.as_slice())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<ResponsePacket> for alloc::vec::Vec<u8> {
+    fn from(packet: ResponsePacket) -> Self {
+        packet.0.to_vec()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<CommandPacket> for alloc::vec::Vec<u8> {
+    fn from(packet: CommandPacket) -> Self {
+        packet.0.to_vec()
+    }
+}
+
 /// Get the command packet with proper header and checksum.
-fn get_command_with_bytes34(command: Command, device_number: u8, byte3: u8, byte4: u8) -> Packet {
+#[inline]
+fn get_command_with_bytes34(
+    command: Command,
+    device_number: u8,
+    byte3: u8,
+    byte4: u8,
+) -> CommandPacket {
+    get_command_with_data(command, device_number, [byte3, byte4, 0x00, 0x00, 0x00])
+}
+
+/// Get the command packet with proper header and checksum, `data` being the 4th to 8th bytes.
+fn get_command_with_data(command: Command, device_number: u8, data: [u8; 5]) -> CommandPacket {
     let mut ret: Packet = [
         0xFF,
         device_number,
         command.get_command_value(),
-        byte3,
-        byte4,
-        0x00,
-        0x00,
-        0x00,
+        data[0],
+        data[1],
+        data[2],
+        data[3],
+        data[4],
         0x00,
     ];
     ret[8] = checksum(&ret[1..8]);
-    ret
+    CommandPacket(ret)
 }
 
 /// Create a command to read the gas concentration of the sensor.
-pub fn read_gas_concentration(device_number: u8) -> Packet {
+pub fn read_gas_concentration(device_number: u8) -> CommandPacket {
     get_command_with_bytes34(Command::ReadGasConcentration, device_number, 0x00, 0x00)
 }
 
+/// Create a command to read the gas concentration of any sensor, see [`BROADCAST_DEVICE_NUMBER`].
+pub fn read_gas_concentration_broadcast() -> CommandPacket {
+    read_gas_concentration(BROADCAST_DEVICE_NUMBER)
+}
+
 /// Create a command to enable or disable Automatic Baseline Correction (ABC)
-pub fn set_automatic_baseline_correction(device_number: u8, enabled: bool) -> Packet {
+pub fn set_automatic_baseline_correction(device_number: u8, enabled: bool) -> CommandPacket {
     get_command_with_bytes34(
         Command::SetAutomaticBaselineCorrection,
         device_number,
@@ -82,170 +376,1620 @@ pub fn set_automatic_baseline_correction(device_number: u8, enabled: bool) -> Pa
     )
 }
 
+/// Create a command to enable or disable Automatic Baseline Correction (ABC), persisting the
+/// setting across power cycles.
+///
+/// Not documented by Winsen, and only understood by some MH-Z19B firmwares: on units where ABC
+/// is enabled again after a power cycle despite [`set_automatic_baseline_correction`] (0x79), this
+/// 0x78 command keeps ABC disabled. Other units may silently ignore it: check the result with
+/// [`read_abc_status`] after a power cycle.
+pub fn set_abc_persistent(device_number: u8, enabled: bool) -> CommandPacket {
+    get_command_with_bytes34(
+        Command::SetPersistentAutomaticBaselineCorrection,
+        device_number,
+        if enabled { 0xA0 } else { 0x00 },
+        0x00,
+    )
+}
+
+/// Interval between two Automatic Baseline Corrections, in hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbcInterval {
+    pub hours: u8,
+}
+
+impl AbcInterval {
+    /// No automatic baseline correction
+    pub const DISABLED: AbcInterval = AbcInterval { hours: 0x00 };
+    /// Datasheet default: one correction every 24 hours
+    pub const DEFAULT: AbcInterval = AbcInterval { hours: 0x18 };
+}
+
+/// Create a command to configure the Automatic Baseline Correction interval.
+///
+/// This is the ABC command (0x79) with the interval in hours as 5th byte: only some
+/// firmware versions take the interval into account, others just enable ABC with their
+/// default 24 hours interval. `AbcInterval::DISABLED` disables ABC.
+pub fn set_abc_interval(device_number: u8, interval: AbcInterval) -> CommandPacket {
+    get_command_with_bytes34(
+        Command::SetAutomaticBaselineCorrection,
+        device_number,
+        if interval == AbcInterval::DISABLED {
+            0x00
+        } else {
+            0xA0
+        },
+        interval.hours,
+    )
+}
+
 /// Create a command to calibrate the span point.
 ///
 /// Quoting the datasheet: "Note: Pls do ZERO calibration before span calibration
 /// Please make sure the sensor worked under a certain level co2 for over 20 minutes.
 ///
 /// Suggest using 2000ppm as span, at least 1000ppm"
-pub fn calibrate_span_point(device_number: u8, value: u16) -> Packet {
-    get_command_with_bytes34(
-        Command::CalibrateSpan,
-        device_number,
-        ((value & 0xff00) >> 8) as u8,
-        (value & 0xff) as u8,
-    )
+///
+/// The value is not checked against this minimum: prefer [`calibrate_span_reference`].
+pub fn calibrate_span_point(device_number: u8, value: u16) -> CommandPacket {
+    let (high, low) = split_u16_be(value);
+    get_command_with_bytes34(Command::CalibrateSpan, device_number, high, low)
+}
+
+/// Create a command to calibrate the span point at a reference of at least 1000ppm, see
+/// [`calibrate_span_point`].
+pub fn calibrate_span_reference(device_number: u8, point: SpanCalibrationPoint) -> CommandPacket {
+    calibrate_span_point(device_number, point.reference_ppm.ppm())
 }
 
 /// Create a command to set the sensor detection range (MH-Z19B only).
 ///
 /// Quoting the datasheet: "Detection range is 2000 or 5000ppm"
-pub fn set_detection_range(device_number: u8, value: u16) -> Packet {
-    get_command_with_bytes34(
-        Command::SetSensorDetectionRange,
-        device_number,
-        ((value & 0xff00) >> 8) as u8,
-        (value & 0xff) as u8,
-    )
+pub fn set_detection_range(device_number: u8, value: u16) -> CommandPacket {
+    let (high, low) = split_u16_be(value);
+    get_command_with_bytes34(Command::SetSensorDetectionRange, device_number, high, low)
 }
 
 /// Create a command to calibrate the zero point.
 ///
 /// Quoting the datasheet: "Note：Zero point is 400ppm, please make sure the sensor has
 /// been worked under 400ppm for over 20 minutes"
-pub fn calibrate_zero_point(device_number: u8) -> Packet {
+pub fn calibrate_zero_point(device_number: u8) -> CommandPacket {
     get_command_with_bytes34(Command::CalibrateZero, device_number, 0x00, 0x00)
 }
 
-/// Implementation of the checksum as defined in https://www.winsen-sensor.com/d/files/PDF/Infrared%20Gas%20Sensor/NDIR%20CO2%20SENSOR/MH-Z19%20CO2%20Ver1.0.pdf
-fn checksum(payload: &[u8]) -> u8 {
-    1u8.wrapping_add(0xff - payload.iter().fold(0u8, |sum, c| sum.wrapping_add(*c)))
+/// Create a command to calibrate the zero point, see [`calibrate_zero_point`].
+///
+/// The command carries no value: the point documents the environment the sensor must be in.
+pub fn calibrate_zero_reference(device_number: u8, _point: ZeroCalibrationPoint) -> CommandPacket {
+    calibrate_zero_point(device_number)
 }
 
-/// Extract the payload from a packet, validating packet length, checksum & header.
-pub fn parse_payload(packet: &[u8]) -> Result<&[u8], MHZ19Error> {
-    use MHZ19Error::*;
-    if packet.len() != 9 {
-        return Err(WrongPacketLength(packet.len()));
+/// Reference concentration of a zero point calibration.
+///
+/// The "calibrate zero point" command carries no value: the sensor takes the concentration of
+/// its environment as 400ppm. This type documents the environment the calibration requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZeroCalibrationPoint {
+    pub reference_ppm: u16,
+}
+
+impl ZeroCalibrationPoint {
+    /// Zero point of the datasheet: outdoor air, 400ppm
+    pub const STANDARD: ZeroCalibrationPoint = ZeroCalibrationPoint { reference_ppm: 400 };
+
+    /// The reference must be close to the 400ppm zero point of the sensor (380 to 420ppm).
+    pub fn new(reference_ppm: u16) -> Self {
+        debug_assert!(
+            (380..=420).contains(&reference_ppm),
+            "zero point reference must be between 380 and 420 ppm, got {}",
+            reference_ppm
+        );
+        ZeroCalibrationPoint { reference_ppm }
     }
-    let header = packet[0];
-    if header != 0xFF {
-        return Err(WrongStartByte(header));
+}
+
+/// Span calibration value, at least 1000ppm as required by the datasheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpanValue(u16);
+
+impl SpanValue {
+    pub const MIN: SpanValue = SpanValue(1000);
+
+    /// `None` below 1000ppm
+    pub fn new(ppm: u16) -> Option<SpanValue> {
+        if ppm >= Self::MIN.0 {
+            Some(SpanValue(ppm))
+        } else {
+            None
+        }
     }
-    let payload = &packet[1..8];
-    let found_checksum = packet[8];
-    let payload_checksum = checksum(payload);
-    if found_checksum != payload_checksum {
-        return Err(WrongChecksum(payload_checksum, found_checksum));
+
+    pub fn ppm(&self) -> u16 {
+        self.0
     }
+}
 
-    Ok(payload)
+/// Reference concentration of a span point calibration, see [`calibrate_span_point`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanCalibrationPoint {
+    pub reference_ppm: SpanValue,
 }
 
-/// Get the CO2 gas concentration in ppm from a response packet.
-///
-/// Will return an error if the packet is not a "read gas concentration packet"
-pub fn parse_gas_concentration_ppm(packet: &[u8]) -> Result<u32, MHZ19Error> {
-    let payload = parse_payload(packet)?;
-    if payload[0] != Command::ReadGasConcentration.get_command_value() {
-        Err(MHZ19Error::WrongPacketType(
-            Command::ReadGasConcentration.get_command_value(),
-            payload[0],
-        ))
-    } else {
-        Ok(256 * (payload[1] as u32) + (payload[2] as u32))
+impl SpanCalibrationPoint {
+    /// Span suggested by the datasheet: 2000ppm
+    pub const RECOMMENDED: SpanCalibrationPoint = SpanCalibrationPoint {
+        reference_ppm: SpanValue(2000),
+    };
+
+    /// `None` below 1000ppm
+    pub fn new(reference_ppm: u16) -> Option<SpanCalibrationPoint> {
+        SpanValue::new(reference_ppm).map(|reference_ppm| SpanCalibrationPoint { reference_ppm })
     }
 }
 
-/// Get the CO2 gas concentration in ppm from a response packet.
+/// Create a command to read the Automatic Baseline Correction status.
 ///
-/// Will return an error if the packet is not a "read gas concentration packet"
-#[deprecated = "Please use `parse_gas_concentration_ppm` instead"]
-pub fn parse_gas_contentration_ppm(packet: &[u8]) -> Result<u32, MHZ19Error> {
-    parse_gas_concentration_ppm(packet)
+/// This command is not documented by Winsen, see [`parse_abc_status`].
+pub fn read_abc_status(device_number: u8) -> CommandPacket {
+    get_command_with_bytes34(
+        Command::ReadAutomaticBaselineCorrection,
+        device_number,
+        0x00,
+        0x00,
+    )
 }
 
-#[derive(Debug, PartialEq)]
-pub enum MHZ19Error {
-    /// Packet of bytes has the wrong size
-    WrongPacketLength(usize),
-    /// Packet of bytes has the wrong checksum
-    WrongChecksum(u8, u8),
-    /// Wrong start byte (must be 0xFF)
-    WrongStartByte(u8),
-    /// The packet type is not the one excepting (eg must be 0x86 when reading gas concentration)
-    WrongPacketType(u8, u8),
+/// Create a command to read the sensor detection range.
+///
+/// This command is not documented by Winsen, see [`parse_detection_range`].
+pub fn read_detection_range(device_number: u8) -> CommandPacket {
+    get_command_with_bytes34(Command::ReadSensorDetectionRange, device_number, 0x00, 0x00)
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for MHZ19Error {}
+/// Create a command to read the firmware version.
+///
+/// This command is not documented by Winsen, see [`parse_firmware_version`].
+pub fn read_firmware_version(device_number: u8) -> CommandPacket {
+    get_command_with_bytes34(Command::ReadFirmwareVersion, device_number, 0x00, 0x00)
+}
 
-impl fmt::Display for MHZ19Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use MHZ19Error::*;
+/// Create a command to write a 16 bits word in the user EEPROM of the sensor.
+///
+/// # Warning
+///
+/// Not documented by Winsen: the 0xC4 command comes from reverse engineering of some MH-Z14A
+/// derivatives. Other sensors may interpret it differently, and writing the wrong address
+/// may overwrite the factory calibration and **brick the sensor**. Only use it on a sensor known
+/// to support it.
+pub fn write_eeprom(device_number: u8, address: u8, data: u16) -> CommandPacket {
+    let (high, low) = split_u16_be(data);
+    get_command_with_data(
+        Command::WriteEeprom,
+        device_number,
+        [address, high, low, 0x00, 0x00],
+    )
+}
+
+/// Create a command to read a 16 bits word of the user EEPROM of the sensor, see
+/// [`parse_eeprom_response`].
+///
+/// Not documented by Winsen, see the warning of [`write_eeprom`].
+pub fn read_eeprom(device_number: u8, address: u8) -> CommandPacket {
+    get_command_with_bytes34(Command::ReadEeprom, device_number, address, 0x00)
+}
+
+impl CommandPacket {
+    /// Get the same command addressed to another device number.
+    pub fn with_device_number(&self, device_number: u8) -> CommandPacket {
+        let mut packet = self.0;
+        packet[1] = device_number;
+        packet[8] = checksum(&packet[1..8]);
+        CommandPacket(packet)
+    }
+}
+
+/// Get a command for each of the given device numbers, eg to configure a batch of sensors.
+///
+/// `command` is built by any command builder, its device number is replaced.
+pub fn broadcast<'a>(
+    command: &CommandPacket,
+    device_numbers: &'a [u8],
+) -> impl Iterator<Item = CommandPacket> + 'a {
+    let command = *command;
+    device_numbers
+        .iter()
+        .map(move |device_number| command.with_device_number(*device_number))
+}
+
+/// Device number of a sensor which has not been assigned another one.
+pub const DEFAULT_DEVICE_NUMBER: u8 = 0x01;
+
+/// Device number addressing all the sensors of the bus (MH-Z14A).
+///
+/// Every sensor answers a broadcast command: the responses collide when several sensors share
+/// the bus. Only use it when a single sensor is connected, eg to query a sensor whose device
+/// number is unknown. See [`broadcast`] to send a command to several device numbers instead.
+pub const BROADCAST_DEVICE_NUMBER: u8 = 0x00;
+
+/// Detection range of the sensor (MH-Z19B and MH-Z19E only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionRange {
+    Ppm2000,
+    Ppm5000,
+    /// MH-Z19E only
+    Ppm10000,
+}
+
+impl DetectionRange {
+    /// Upper bound of the range in ppm
+    pub fn value(&self) -> u16 {
         match self {
-            WrongChecksum(expected, found) => write!(
-                f,
-                "Invalid checksum, expected {:X}, found {:X}",
-                expected, found
-            ),
-            WrongPacketLength(found) => {
-                write!(f, "Wrong packet length, expected 9, found {}", found)
-            }
-            WrongStartByte(found) => {
-                write!(f, "Wrong start byte, expected 0xFF, found {:X}", found)
-            }
-            WrongPacketType(expected, found) => write!(
-                f,
-                "Wrong packet type, expected {}, found {:X}",
-                expected, found
-            ),
+            DetectionRange::Ppm2000 => 2000,
+            DetectionRange::Ppm5000 => 5000,
+            DetectionRange::Ppm10000 => 10000,
+        }
+    }
+
+    /// Get the detection range matching a ppm value, if any.
+    pub fn from_ppm(ppm: u16) -> Option<DetectionRange> {
+        match ppm {
+            2000 => Some(DetectionRange::Ppm2000),
+            5000 => Some(DetectionRange::Ppm5000),
+            10000 => Some(DetectionRange::Ppm10000),
+            _ => None,
         }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+impl core::fmt::Display for DetectionRange {
+    /// The upper bound of the range, eg "2000 ppm"
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} ppm", self.value())
+    }
+}
 
-    /// The command payload used to query the co2 cas concentration
-    /// from the device number 1 (the default device number)
-    ///
-    /// It is the same value as the result of
-    /// get_command_packet(Command::ReadGasConcentration, 1).
-    static READ_GAS_CONCENTRATION_COMMAND_ON_DEV1_PACKET: &'static [u8] =
-        &[0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
+/// A sensor identified by its device number, creating the commands addressed to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sensor {
+    pub device_number: u8,
+}
 
-    #[test]
-    fn test_checksum() {
-        assert_eq!(0x79, checksum(&[0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00]));
-        assert_eq!(0xA0, checksum(&[0x01, 0x88, 0x07, 0xD0, 0x00, 0x00, 0x00]));
-        assert_eq!(0xD1, checksum(&[0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00]));
+impl Default for Sensor {
+    /// Sensor with the default device number (0x01)
+    fn default() -> Self {
+        Sensor {
+            device_number: DEFAULT_DEVICE_NUMBER,
+        }
     }
+}
 
-    #[test]
-    fn test_get_payload() {
-        assert_eq!(Err(MHZ19Error::WrongPacketLength(0)), parse_payload(&[]));
-        assert_eq!(Err(MHZ19Error::WrongPacketLength(1)), parse_payload(&[12]));
-        assert_eq!(
-            Err(MHZ19Error::WrongPacketLength(12)),
-            parse_payload(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])
-        );
-        assert_eq!(
-            Err(MHZ19Error::WrongStartByte(10)),
-            parse_payload(&[10, 2, 3, 4, 5, 6, 7, 8, 9])
-        );
-        assert_eq!(
-            Err(MHZ19Error::WrongChecksum(221, 9)),
-            parse_payload(&[0xFF, 2, 3, 4, 5, 6, 7, 8, 9])
+impl Sensor {
+    pub fn new(device_number: u8) -> Self {
+        Sensor { device_number }
+    }
+
+    /// See [`read_gas_concentration`]
+    pub fn read_gas_concentration(&self) -> CommandPacket {
+        read_gas_concentration(self.device_number)
+    }
+
+    /// See [`set_automatic_baseline_correction`]
+    pub fn set_automatic_baseline_correction(&self, enabled: bool) -> CommandPacket {
+        set_automatic_baseline_correction(self.device_number, enabled)
+    }
+
+    /// See [`set_detection_range`]
+    pub fn set_detection_range(&self, range: DetectionRange) -> CommandPacket {
+        set_detection_range(self.device_number, range.value())
+    }
+
+    /// See [`calibrate_zero_point`]
+    pub fn calibrate_zero_point(&self, point: ZeroCalibrationPoint) -> CommandPacket {
+        calibrate_zero_reference(self.device_number, point)
+    }
+
+    /// See [`calibrate_span_point`]
+    pub fn calibrate_span_point(&self, point: SpanCalibrationPoint) -> CommandPacket {
+        calibrate_span_reference(self.device_number, point)
+    }
+}
+
+/// Sensor models supported by this crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorModel {
+    MHZ19,
+    MHZ19B,
+    /// MH-Z19 variant with a 10000 ppm detection range
+    MHZ19E,
+    MHZ14,
+}
+
+/// Settings of a sensor, see [`SensorBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorConfig {
+    pub device_number: u8,
+    pub model: SensorModel,
+    pub detection_range: DetectionRange,
+    /// Automatic Baseline Correction
+    pub abc_enabled: bool,
+}
+
+impl Default for SensorConfig {
+    /// MH-Z19B factory settings: default device number, 5000ppm detection range with ABC enabled
+    fn default() -> Self {
+        SensorConfig {
+            device_number: DEFAULT_DEVICE_NUMBER,
+            model: SensorModel::MHZ19B,
+            detection_range: DetectionRange::Ppm5000,
+            abc_enabled: true,
+        }
+    }
+}
+
+impl SensorConfig {
+    /// The sensor addressed by the configured device number
+    pub fn sensor(&self) -> Sensor {
+        Sensor::new(self.device_number)
+    }
+
+    /// See [`read_gas_concentration`]
+    pub fn read_gas_concentration(&self) -> CommandPacket {
+        self.sensor().read_gas_concentration()
+    }
+
+    /// See [`calibrate_zero_point`]
+    pub fn calibrate_zero_point(&self, point: ZeroCalibrationPoint) -> CommandPacket {
+        self.sensor().calibrate_zero_point(point)
+    }
+
+    /// See [`calibrate_span_point`]
+    pub fn calibrate_span_point(&self, point: SpanCalibrationPoint) -> CommandPacket {
+        self.sensor().calibrate_span_point(point)
+    }
+
+    /// Commands applying the configured detection range and ABC to the sensor
+    pub fn apply_commands(&self) -> [CommandPacket; 2] {
+        let sensor = self.sensor();
+        [
+            sensor.set_detection_range(self.detection_range),
+            sensor.set_automatic_baseline_correction(self.abc_enabled),
+        ]
+    }
+}
+
+/// Commands converging the settings of a sensor from `current` (eg as read from the sensor) to
+/// `desired`, for idempotent provisioning: nothing is sent for the settings already matching.
+///
+/// The commands are addressed to the device number of `current`; the device number and model
+/// cannot be changed by a command and are ignored.
+pub fn config_diff(
+    current: &SensorConfig,
+    desired: &SensorConfig,
+) -> impl Iterator<Item = CommandPacket> {
+    let sensor = current.sensor();
+    let detection_range = if current.detection_range != desired.detection_range {
+        Some(sensor.set_detection_range(desired.detection_range))
+    } else {
+        None
+    };
+    let abc = if current.abc_enabled != desired.abc_enabled {
+        Some(sensor.set_automatic_baseline_correction(desired.abc_enabled))
+    } else {
+        None
+    };
+    detection_range.into_iter().chain(abc)
+}
+
+/// Builder of a [`SensorConfig`], starting from the default configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SensorBuilder {
+    config: SensorConfig,
+}
+
+impl SensorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_device_number(mut self, device_number: u8) -> Self {
+        self.config.device_number = device_number;
+        self
+    }
+
+    pub fn with_model(mut self, model: SensorModel) -> Self {
+        self.config.model = model;
+        self
+    }
+
+    pub fn with_detection_range(mut self, detection_range: DetectionRange) -> Self {
+        self.config.detection_range = detection_range;
+        self
+    }
+
+    pub fn with_abc(mut self, enabled: bool) -> Self {
+        self.config.abc_enabled = enabled;
+        self
+    }
+
+    pub fn build(self) -> SensorConfig {
+        self.config
+    }
+}
+
+/// Durations defined by the datasheets, usable without std.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SensorDuration(pub core::time::Duration);
+
+impl SensorDuration {
+    /// Preheat time after power on, before the readings are reliable (3 minutes)
+    pub const WARMUP: SensorDuration = SensorDuration(core::time::Duration::from_secs(3 * 60));
+    /// Time the sensor must spend in a stable environment before a calibration (20 minutes)
+    pub const CALIBRATION_STABILISATION: SensorDuration =
+        SensorDuration(core::time::Duration::from_secs(20 * 60));
+    /// Period of the automatic baseline correction (24 hours)
+    pub const ABC_PERIOD: SensorDuration =
+        SensorDuration(core::time::Duration::from_secs(24 * 60 * 60));
+    /// Time after which a command left unanswered can be considered lost (1 second)
+    pub const COMMAND_RESPONSE_TIMEOUT: SensorDuration =
+        SensorDuration(core::time::Duration::from_secs(1));
+
+    pub const fn as_duration(self) -> core::time::Duration {
+        self.0
+    }
+}
+
+impl From<SensorDuration> for core::time::Duration {
+    fn from(duration: SensorDuration) -> Self {
+        duration.0
+    }
+}
+
+/// Actions after which the sensor readings need some time to stabilize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StabilisationCause {
+    /// Zero point calibration (`calibrate_zero_point`)
+    ZeroCalibration,
+    /// Span point calibration (`calibrate_span_point`)
+    SpanCalibration,
+    /// Enabling Automatic Baseline Correction (`set_automatic_baseline_correction`)
+    EnableAutomaticBaselineCorrection,
+}
+
+/// Get the datasheet recommended time to wait before trusting the sensor readings
+/// after the given action.
+///
+/// - zero and span point calibrations require the sensor to be in a stable environment for
+///   20 minutes,
+/// - the automatic baseline correction is performed every 24 hours after power on
+///   (MH-Z19B datasheet).
+///
+/// This is only an advisory value.
+pub fn recommended_wait(cause: StabilisationCause) -> core::time::Duration {
+    use StabilisationCause::*;
+    match cause {
+        ZeroCalibration | SpanCalibration => SensorDuration::CALIBRATION_STABILISATION.0,
+        EnableAutomaticBaselineCorrection => SensorDuration::ABC_PERIOD.0,
+    }
+}
+
+/// Split a 16 bits value into its (high, low) bytes: the protocol is big endian.
+pub fn split_u16_be(value: u16) -> (u8, u8) {
+    ((value >> 8) as u8, (value & 0xff) as u8)
+}
+
+/// Join (high, low) bytes into a 16 bits value: the protocol is big endian.
+pub fn join_u16_be(high: u8, low: u8) -> u16 {
+    ((high as u16) << 8) | low as u16
+}
+
+/// Signed difference between two concentrations, saturating at the `i32` bounds.
+pub fn ppm_delta(previous: u32, current: u32) -> i32 {
+    let delta = current as i64 - previous as i64;
+    delta.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// Rate of change between two concentrations read `elapsed_ms` milliseconds apart, in ppm per
+/// minute.
+///
+/// Returns `None` if no time elapsed.
+pub fn ppm_rate_per_minute(previous: u32, current: u32, elapsed_ms: u32) -> Option<f32> {
+    if elapsed_ms == 0 {
+        return None;
+    }
+    Some(ppm_delta(previous, current) as f32 * 60_000.0 / elapsed_ms as f32)
+}
+
+/// Inclusive range of concentrations, eg to define alarm thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PpmRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl PpmRange {
+    /// Concentrations of a well ventilated room
+    pub const SAFE: PpmRange = PpmRange {
+        min: 400,
+        max: 1000,
+    };
+    /// Concentrations measurable with the default detection range
+    pub const SENSOR: PpmRange = PpmRange { min: 0, max: 5000 };
+    pub const FULL: PpmRange = PpmRange {
+        min: 0,
+        max: u32::MAX,
+    };
+
+    pub fn new(min: u32, max: u32) -> Self {
+        debug_assert!(min <= max, "min must not be greater than max");
+        PpmRange { min, max }
+    }
+
+    pub fn contains(&self, ppm: u32) -> bool {
+        self.min <= ppm && ppm <= self.max
+    }
+
+    /// The closest concentration of the range
+    pub fn clamp(&self, ppm: u32) -> u32 {
+        ppm.clamp(self.min, self.max)
+    }
+
+    pub fn overlaps(&self, other: &PpmRange) -> bool {
+        self.min <= other.max && other.min <= self.max
+    }
+}
+
+/// Implementation of the checksum as defined in https://www.winsen-sensor.com/d/files/PDF/Infrared%20Gas%20Sensor/NDIR%20CO2%20SENSOR/MH-Z19%20CO2%20Ver1.0.pdf
+#[inline]
+const fn checksum(payload: &[u8]) -> u8 {
+    let mut sum = 0u8;
+    let mut i = 0;
+    while i < payload.len() {
+        sum = sum.wrapping_add(payload[i]);
+        i += 1;
+    }
+    1u8.wrapping_add(0xff - sum)
+}
+
+// Checked at compile time: any change to the checksum algorithm breaks the build.
+const _: () = assert!(checksum(&[0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00]) == 0x79);
+const _: () = assert!(checksum(&[0x01, 0x88, 0x07, 0xD0, 0x00, 0x00, 0x00]) == 0xA0);
+const _: () = assert!(checksum(&[0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00]) == 0xD1);
+
+/// Checksum of a whole packet, computed on its 2nd to 8th bytes.
+///
+/// Usable in const context to check frames embedded as static data at compile time, eg
+/// `const _: () = assert!(FRAME[8] == packet_checksum(&FRAME));`.
+pub const fn packet_checksum(packet: &Packet) -> u8 {
+    checksum(&[
+        packet[1], packet[2], packet[3], packet[4], packet[5], packet[6], packet[7],
+    ])
+}
+
+/// Compare two packets ignoring their checksum byte.
+///
+/// Useful to compare a command against a packet captured on the wire: a checksum
+/// mismatch can then be checked separately.
+pub fn frames_equal_ignoring_checksum(a: &Packet, b: &Packet) -> bool {
+    a[..8] == b[..8]
+}
+
+/// Extract the payload from a packet, validating packet length, checksum & header.
+///
+/// A packet made only of 0xFF or only of 0x00 bytes is reported as `NoSensorResponse`: it is
+/// what an uart with no sensor connected reads.
+#[inline]
+pub fn parse_payload(packet: &[u8]) -> Result<&[u8], MHZ19Error> {
+    use MHZ19Error::*;
+    if packet.len() != 9 {
+        return Err(WrongPacketLength(packet.len()));
+    }
+    if packet.iter().all(|b| *b == 0xFF) || packet.iter().all(|b| *b == 0x00) {
+        return Err(NoSensorResponse);
+    }
+    let header = packet[0];
+    if header != 0xFF {
+        return Err(WrongStartByte(header));
+    }
+    let payload = &packet[1..8];
+    let found_checksum = packet[8];
+    let payload_checksum = checksum(payload);
+    if found_checksum != payload_checksum {
+        return Err(WrongChecksum(payload_checksum, found_checksum));
+    }
+
+    Ok(payload)
+}
+
+/// Extract the payload from a packet embedded at the given offset of a larger buffer, see
+/// [`parse_payload`].
+///
+/// Will return a `WrongPacketLength` error with the number of bytes available after the offset
+/// if the buffer is too short to hold a packet at this offset.
+pub fn parse_payload_at(buffer: &[u8], offset: usize) -> Result<&[u8], MHZ19Error> {
+    match buffer.get(offset..).and_then(|tail| tail.get(..9)) {
+        Some(packet) => parse_payload(packet),
+        None => Err(MHZ19Error::WrongPacketLength(
+            buffer.len().saturating_sub(offset),
+        )),
+    }
+}
+
+/// Parse a packet written as hexadecimal bytes, eg `"FF 01 86 00 00 00 00 00 79"` pasted from
+/// a log, and validate it (see [`parse_payload`]).
+///
+/// Whitespace is ignored. Will return an `InvalidHex` error at the position of the first non
+/// hexadecimal character or of a last unpaired digit, and a `WrongPacketLength` error if the
+/// string does not hold 9 bytes.
+pub fn parse_hex_frame(s: &str) -> Result<Packet, MHZ19Error> {
+    let mut packet = [0u8; 9];
+    let mut len = 0;
+    let mut high_digit = None;
+    for (position, c) in s.char_indices() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let digit = c.to_digit(16).ok_or(MHZ19Error::InvalidHex { position })? as u8;
+        match high_digit.take() {
+            None => high_digit = Some((position, digit)),
+            Some((_, high)) => {
+                if let Some(byte) = packet.get_mut(len) {
+                    *byte = (high << 4) | digit;
+                }
+                len += 1;
+            }
+        }
+    }
+    if let Some((position, _)) = high_digit {
+        return Err(MHZ19Error::InvalidHex { position });
+    }
+    if len != packet.len() {
+        return Err(MHZ19Error::WrongPacketLength(len));
+    }
+    parse_payload(&packet)?;
+    Ok(packet)
+}
+
+/// Payload of a packet copied out of the packet buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OwnedPayload {
+    /// Command byte (2nd byte of the packet)
+    pub command: u8,
+    /// 3rd to 8th bytes of the packet
+    pub data: [u8; 6],
+}
+
+/// Extract the payload from a packet like `parse_payload`, copying it so that it does not
+/// borrow the packet buffer.
+pub fn parse_payload_owned(packet: &[u8]) -> Result<OwnedPayload, MHZ19Error> {
+    let payload = parse_payload(packet)?;
+    let mut data = [0u8; 6];
+    data.copy_from_slice(&payload[1..]);
+    Ok(OwnedPayload {
+        command: payload[0],
+        data,
+    })
+}
+
+/// Options of the lenient parsing functions.
+///
+/// The default options are as strict as `parse_payload`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Strip trailing CR (`\r`) and LF (`\n`) bytes appended by terminal tools before
+    /// validating the packet.
+    ///
+    /// Only bytes after the 9th byte are stripped because the checksum byte itself
+    /// may legitimately be 0x0D or 0x0A.
+    pub strip_trailing_crlf: bool,
+    /// Byte order of the concentration, see [`parse_gas_concentration_ppm_with_options`]
+    pub byte_order: ByteOrder,
+}
+
+/// Byte order of the 2 bytes values of the responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Most significant byte first, as documented by Winsen
+    #[default]
+    Big,
+    /// Least significant byte first, as sent by some clones
+    Little,
+}
+
+impl ByteOrder {
+    /// Get the value of 2 consecutive bytes of a packet.
+    pub fn join_u16(self, first: u8, second: u8) -> u16 {
+        match self {
+            ByteOrder::Big => join_u16_be(first, second),
+            ByteOrder::Little => join_u16_be(second, first),
+        }
+    }
+}
+
+/// Extract the payload from a packet, validating packet length, checksum & header,
+/// with the given parsing options.
+pub fn parse_payload_with_options<'a>(
+    packet: &'a [u8],
+    options: &ParseOptions,
+) -> Result<&'a [u8], MHZ19Error> {
+    let mut packet = packet;
+    if options.strip_trailing_crlf {
+        while packet.len() > 9 && matches!(packet[packet.len() - 1], b'\r' | b'\n') {
+            packet = &packet[..packet.len() - 1];
+        }
+    }
+    parse_payload(packet)
+}
+
+/// Extract the payload from a response packet like `parse_payload`, also checking that the
+/// bytes not used by known responses are 0x00.
+///
+/// Known responses use at most the 2nd to 5th bytes, the 6th to 8th bytes are reserved except
+/// for the temperature & status of the gas concentration response, the version of the
+/// firmware version response and the status of the ABC status response. Packets of unknown
+/// types are only checked like `parse_payload` does.
+///
+/// Unexpected reserved bytes may reveal firmware variants with different packet layouts.
+pub fn parse_payload_strict(packet: &[u8]) -> Result<&[u8], MHZ19Error> {
+    let payload = parse_payload(packet)?;
+    if let Some(command) = Command::from_command_value(payload[0]) {
+        for position in command.get_reserved_response_bytes() {
+            let value = packet[*position];
+            if value != 0x00 {
+                return Err(MHZ19Error::UnexpectedReservedByte {
+                    position: *position as u8,
+                    value,
+                });
+            }
+        }
+    }
+    Ok(payload)
+}
+
+/// Outcome of the checks of a frame received from the sensor, see [`diagnose_frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDiagnosis {
+    /// The frame is a valid response of a known type
+    Valid,
+    /// The frame is not 9 bytes long
+    WrongLength { found: usize },
+    /// The first byte is not 0xFF.
+    ///
+    /// `start_byte_position` is the position of a start byte later in the frame, if any: the
+    /// frame is then likely misaligned rather than corrupted.
+    WrongStartByte {
+        found: u8,
+        start_byte_position: Option<usize>,
+    },
+    /// The header is fine, but the checksum byte does not match the payload: either a payload
+    /// byte or the checksum byte itself is corrupted
+    WrongChecksum { expected: u8, found: u8 },
+    /// The frame is valid, but its type (2nd byte) is not known by this crate
+    UnknownPacketType(u8),
+    /// The frame is valid, but a reserved byte is not 0x00 (see [`parse_payload_strict`])
+    UnexpectedReservedByte { position: u8, value: u8 },
+    /// The frame is only made of 0xFF or 0x00 bytes: the sensor is likely not connected
+    NoSensorResponse,
+    /// The frame was rejected for another reason (not reported by the current checks)
+    OtherError,
+}
+
+/// Check a frame received from the sensor, reporting the first failing check.
+///
+/// Gives more details than the parsing errors, for hardware bring-up tools.
+pub fn diagnose_frame(packet: &[u8]) -> FrameDiagnosis {
+    match parse_payload_strict(packet) {
+        Ok(payload) => match Command::from_command_value(payload[0]) {
+            Some(_) => FrameDiagnosis::Valid,
+            None => FrameDiagnosis::UnknownPacketType(payload[0]),
+        },
+        Err(MHZ19Error::WrongPacketLength(found)) => FrameDiagnosis::WrongLength { found },
+        Err(MHZ19Error::WrongStartByte(found)) => FrameDiagnosis::WrongStartByte {
+            found,
+            start_byte_position: packet.iter().position(|b| *b == 0xFF),
+        },
+        Err(MHZ19Error::WrongChecksum(expected, found)) => {
+            FrameDiagnosis::WrongChecksum { expected, found }
+        }
+        Err(MHZ19Error::UnexpectedReservedByte { position, value }) => {
+            FrameDiagnosis::UnexpectedReservedByte { position, value }
+        }
+        Err(MHZ19Error::NoSensorResponse) => FrameDiagnosis::NoSensorResponse,
+        Err(MHZ19Error::WrongPacketType(_, _))
+        | Err(MHZ19Error::InvalidFirmwareVersion(_))
+        | Err(MHZ19Error::ResyncFailed)
+        | Err(MHZ19Error::InvalidHex { .. }) => FrameDiagnosis::OtherError,
+    }
+}
+
+/// Indices in the payload (0 being the command byte) of the non zero bytes of a valid packet.
+///
+/// Helps finding out which bytes an undocumented response actually uses.
+pub fn nonzero_fields(packet: &[u8]) -> Result<impl Iterator<Item = usize> + '_, MHZ19Error> {
+    let payload = parse_payload(packet)?;
+    Ok(payload
+        .iter()
+        .enumerate()
+        .filter(|(_, byte)| **byte != 0)
+        .map(|(index, _)| index))
+}
+
+/// Get the CO2 gas concentration in ppm from a response packet.
+///
+/// Will return an error if the packet is not a "read gas concentration packet"
+#[inline]
+pub fn parse_gas_concentration_ppm(packet: &[u8]) -> Result<u32, MHZ19Error> {
+    let payload = parse_response_payload(packet, Command::ReadGasConcentration)?;
+    Ok(join_u16_be(payload[1], payload[2]) as u32)
+}
+
+/// Get the CO2 gas concentration in ppm from a response packet, parsed with the given options.
+///
+/// With `ByteOrder::Little`, supports clones sending the concentration least significant byte
+/// first.
+pub fn parse_gas_concentration_ppm_with_options(
+    packet: &[u8],
+    options: &ParseOptions,
+) -> Result<u32, MHZ19Error> {
+    let payload = check_packet_type(
+        parse_payload_with_options(packet, options)?,
+        Command::ReadGasConcentration,
+    )?;
+    Ok(options.byte_order.join_u16(payload[1], payload[2]) as u32)
+}
+
+/// Response to a "read gas concentration" command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasReading {
+    pub co2_ppm: u32,
+    /// Temperature of the sensor.
+    ///
+    /// Not documented by Winsen: it is the 5th byte of the response minus 40, and is not
+    /// accurate enough to be used as an ambient temperature.
+    pub temperature_celsius: i16,
+    /// Status byte, see [`parse_status_flags`]
+    pub status: u8,
+}
+
+/// Get all the values of a "read gas concentration" response packet.
+///
+/// Will return an error if the packet is not a "read gas concentration packet"
+pub fn parse_gas_reading(packet: &[u8]) -> Result<GasReading, MHZ19Error> {
+    let payload = parse_response_payload(packet, Command::ReadGasConcentration)?;
+    Ok(GasReading {
+        co2_ppm: join_u16_be(payload[1], payload[2]) as u32,
+        temperature_celsius: payload[3] as i16 - 40,
+        status: payload[4],
+    })
+}
+
+/// Build a valid "read gas concentration" response, the inverse of [`parse_gas_reading`], to
+/// test code handling the responses (`test-helpers` feature).
+///
+/// Panics if `ppm` does not fit in 16 bits or `temperature_celsius` is not between -40 and 215,
+/// the values that the response can encode.
+#[cfg(any(test, feature = "test-helpers"))]
+pub fn build_gas_concentration_response(ppm: u32, temperature_celsius: i16, status: u8) -> Packet {
+    assert!(ppm <= u16::MAX as u32, "{} ppm cannot be encoded", ppm);
+    assert!(
+        (-40..=215).contains(&temperature_celsius),
+        "{}°C cannot be encoded",
+        temperature_celsius
+    );
+    let (high, low) = split_u16_be(ppm as u16);
+    let mut packet = [
+        0xFF,
+        Command::ReadGasConcentration.get_command_value(),
+        high,
+        low,
+        (temperature_celsius + 40) as u8,
+        status,
+        0x00,
+        0x00,
+        0x00,
+    ];
+    packet[8] = checksum(&packet[1..8]);
+    packet
+}
+
+/// Change between two consecutive readings, see [`diff_readings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasReadingDiff {
+    pub ppm_delta: i32,
+    pub temperature_delta_celsius: i16,
+    pub elapsed_seconds: u32,
+}
+
+impl GasReadingDiff {
+    pub fn is_increasing(&self) -> bool {
+        self.ppm_delta > 0
+    }
+
+    /// Did the concentration change by at most `tolerance_ppm`?
+    pub fn is_stable(&self, tolerance_ppm: u32) -> bool {
+        self.ppm_delta.unsigned_abs() <= tolerance_ppm
+    }
+
+    /// Rate of change of the concentration, 0 if no time elapsed
+    pub fn rate_ppm_per_minute(&self) -> f32 {
+        if self.elapsed_seconds == 0 {
+            return 0.0;
+        }
+        self.ppm_delta as f32 * 60.0 / self.elapsed_seconds as f32
+    }
+}
+
+/// Compute the change between two readings made `elapsed_seconds` apart, saturating on overflow.
+pub fn diff_readings(
+    previous: &GasReading,
+    current: &GasReading,
+    elapsed_seconds: u32,
+) -> GasReadingDiff {
+    GasReadingDiff {
+        ppm_delta: ppm_delta(previous.co2_ppm, current.co2_ppm),
+        temperature_delta_celsius: current
+            .temperature_celsius
+            .saturating_sub(previous.temperature_celsius),
+        elapsed_seconds,
+    }
+}
+
+/// Get the Automatic Baseline Correction status from a response packet.
+///
+/// Not documented by Winsen: the 8th byte of the response is 1 when ABC is enabled.
+///
+/// Will return an error if the packet is not a "read ABC status" packet
+pub fn parse_abc_status(packet: &[u8]) -> Result<bool, MHZ19Error> {
+    let payload = parse_response_payload(packet, Command::ReadAutomaticBaselineCorrection)?;
+    Ok(payload[6] == 1)
+}
+
+/// Get the detection range in ppm from a response packet.
+///
+/// Not documented by Winsen: the range is encoded as the 5th and 6th bytes of the response.
+///
+/// Will return an error if the packet is not a "read detection range" packet
+pub fn parse_detection_range(packet: &[u8]) -> Result<u16, MHZ19Error> {
+    let payload = parse_response_payload(packet, Command::ReadSensorDetectionRange)?;
+    Ok(join_u16_be(payload[3], payload[4]))
+}
+
+/// Get the word read from the user EEPROM from a response packet.
+///
+/// Not documented by Winsen: the 4th and 5th bytes of the response hold the word, the 3rd byte
+/// being the address.
+///
+/// Will return an error if the packet is not a "read EEPROM" packet
+pub fn parse_eeprom_response(packet: &[u8]) -> Result<u16, MHZ19Error> {
+    let payload = parse_response_payload(packet, Command::ReadEeprom)?;
+    Ok(join_u16_be(payload[2], payload[3]))
+}
+
+/// Firmware version of the sensor, eg 4.43.
+///
+/// Versions are ordered, eg to enable a feature from a given version:
+/// `version >= FirmwareVersion { major: 4, minor: 43, patch: 0 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion {
+    pub major: u8,
+    pub minor: u8,
+    /// 0 for the firmwares reporting only a major and minor version
+    pub patch: u8,
+}
+
+impl FirmwareVersion {
+    /// Decode the version from the payload (2nd to 8th bytes) of a "read firmware version"
+    /// response, `None` if the encoding is not known.
+    ///
+    /// The only encoding known so far is the ASCII string of the 3rd to 6th bytes of the
+    /// response, eg `"0443"` for version 4.43.0.
+    pub fn parse_from_bytes(bytes: &[u8; 7]) -> Option<FirmwareVersion> {
+        let version = &bytes[1..5];
+        if !version.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let digit = |i: usize| version[i] - b'0';
+        Some(FirmwareVersion {
+            major: digit(0) * 10 + digit(1),
+            minor: digit(2) * 10 + digit(3),
+            patch: 0,
+        })
+    }
+}
+
+impl core::fmt::Display for FirmwareVersion {
+    /// eg "v4.43.0"
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Get the firmware version from a response packet.
+///
+/// Not documented by Winsen: the version is the ASCII string found in the 3rd to 6th bytes
+/// of the response, eg `"0443"` for version 4.43.
+///
+/// Will return an error if the packet is not a "read firmware version" packet
+pub fn parse_firmware_version(packet: &[u8]) -> Result<FirmwareVersion, MHZ19Error> {
+    let payload = parse_response_payload(packet, Command::ReadFirmwareVersion)?;
+    let mut bytes = [0u8; 7];
+    bytes.copy_from_slice(payload);
+    FirmwareVersion::parse_from_bytes(&bytes).ok_or_else(|| {
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&payload[1..5]);
+        MHZ19Error::InvalidFirmwareVersion(version)
+    })
+}
+
+/// Outcome of a zero point calibration reported by the sensor, see [`parse_calibration_result`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationOutcome {
+    Accepted,
+    /// The sensor refused the calibration, with the status byte it reported
+    Rejected(u8),
+}
+
+/// Get the outcome of a zero point calibration from the acknowledgement packet.
+///
+/// Not documented by Winsen and only sent by some firmwares: the acknowledgement echoes the
+/// "calibrate zero point" command byte and its 3rd byte is the status, `0x00` meaning the
+/// calibration was accepted. Any other value is reported as [`CalibrationOutcome::Rejected`].
+///
+/// Will return an error if the packet is not a "calibrate zero point" packet
+pub fn parse_calibration_result(packet: &[u8]) -> Result<CalibrationOutcome, MHZ19Error> {
+    let payload = parse_response_payload(packet, Command::CalibrateZero)?;
+    Ok(match payload[1] {
+        0x00 => CalibrationOutcome::Accepted,
+        status => CalibrationOutcome::Rejected(status),
+    })
+}
+
+/// Extract the payload from a packet, also checking the packet is a response to the given command.
+fn parse_response_payload(packet: &[u8], command: Command) -> Result<&[u8], MHZ19Error> {
+    check_packet_type(parse_payload(packet)?, command)
+}
+
+/// Check the payload is the one of a response to the given command.
+fn check_packet_type(payload: &[u8], command: Command) -> Result<&[u8], MHZ19Error> {
+    if payload[0] != command.get_command_value() {
+        Err(MHZ19Error::WrongPacketType(
+            command.get_command_value(),
+            payload[0],
+        ))
+    } else {
+        Ok(payload)
+    }
+}
+
+/// Bit masks used to decode the status byte of a "read gas concentration" response.
+///
+/// The status byte (6th byte of the response) is not documented by Winsen: the default masks
+/// are community observations and may not match every firmware, hence their configurability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusMasks {
+    /// Set when the sensor is operating normally (bit 6)
+    pub valid: u8,
+    /// Set while the sensor is warming up (bit 4)
+    pub warmup: u8,
+    /// Set when the sensor reports a fault (bit 7)
+    pub fault: u8,
+}
+
+impl StatusMasks {
+    /// Default masks: valid is bit 6 (0x40), warmup is bit 4 (0x10), fault is bit 7 (0x80)
+    pub const DEFAULT: StatusMasks = StatusMasks {
+        valid: 0x40,
+        warmup: 0x10,
+        fault: 0x80,
+    };
+}
+
+/// Flags decoded from the status byte of a "read gas concentration" response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusFlags {
+    /// The sensor is operating normally
+    pub valid: bool,
+    /// The sensor is warming up
+    pub warmup: bool,
+    /// The sensor reports a fault
+    pub fault: bool,
+}
+
+/// Decode the status byte of a "read gas concentration" response using the default masks.
+///
+/// Will return an error if the packet is not a "read gas concentration packet"
+pub fn parse_status_flags(packet: &[u8]) -> Result<StatusFlags, MHZ19Error> {
+    parse_status_flags_with_masks(packet, &StatusMasks::DEFAULT)
+}
+
+/// Decode the status byte of a "read gas concentration" response using custom masks.
+///
+/// Will return an error if the packet is not a "read gas concentration packet"
+pub fn parse_status_flags_with_masks(
+    packet: &[u8],
+    masks: &StatusMasks,
+) -> Result<StatusFlags, MHZ19Error> {
+    let status = parse_response_payload(packet, Command::ReadGasConcentration)?[4];
+    Ok(StatusFlags {
+        valid: status & masks.valid != 0,
+        warmup: status & masks.warmup != 0,
+        fault: status & masks.fault != 0,
+    })
+}
+
+/// Get the CO2 gas concentration in ppm from a response packet.
+///
+/// Will return an error if the packet is not a "read gas concentration packet"
+#[deprecated = "Please use `parse_gas_concentration_ppm` instead"]
+pub fn parse_gas_contentration_ppm(packet: &[u8]) -> Result<u32, MHZ19Error> {
+    parse_gas_concentration_ppm(packet)
+}
+
+/// Errors that may occur while parsing a packet.
+///
+/// With the `std` feature, `MHZ19Error` implements `std::error::Error` and is `Send + Sync`:
+/// it can be propagated with `?` into `Box<dyn std::error::Error>` and
+/// `Box<dyn std::error::Error + Send + Sync>` thanks to the standard library blanket `From` impls.
+///
+/// Without `std`, the `core-error` feature implements `core::error::Error` (Rust 1.81 or later).
+///
+/// The enum is `#[non_exhaustive]`: variants may be added without a breaking release, so
+/// matches outside of this crate need a wildcard arm.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MHZ19Error {
+    /// Packet of bytes has the wrong size
+    WrongPacketLength(usize),
+    /// Packet of bytes has the wrong checksum
+    WrongChecksum(u8, u8),
+    /// Wrong start byte (must be 0xFF)
+    WrongStartByte(u8),
+    /// The packet type is not the one excepting (eg must be 0x86 when reading gas concentration)
+    WrongPacketType(u8, u8),
+    /// The firmware version is not made of 4 ASCII digits
+    InvalidFirmwareVersion([u8; 4]),
+    /// A reserved byte of the packet is not 0x00 (see `parse_payload_strict`)
+    UnexpectedReservedByte { position: u8, value: u8 },
+    /// The packet is only made of 0xFF or 0x00 bytes: the sensor is likely not connected
+    NoSensorResponse,
+    /// No start byte was found in the bytes received, see
+    /// [`assembler::PacketAssembler::with_max_resync_bytes`]
+    ResyncFailed,
+    /// The string is not made of pairs of hexadecimal digits, see [`parse_hex_frame`]
+    InvalidHex { position: usize },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MHZ19Error {}
+
+/// `std::error::Error` is the same trait, implemented above with `std`.
+#[cfg(all(feature = "core-error", not(feature = "std")))]
+impl core::error::Error for MHZ19Error {}
+
+impl core::fmt::Display for MHZ19Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        use MHZ19Error::*;
+        match self {
+            WrongChecksum(expected, found) => write!(
+                f,
+                "Invalid checksum, expected {:X}, found {:X}",
+                expected, found
+            ),
+            WrongPacketLength(found) => {
+                write!(f, "Wrong packet length, expected 9, found {}", found)
+            }
+            WrongStartByte(found) => {
+                write!(f, "Wrong start byte, expected 0xFF, found {:X}", found)
+            }
+            WrongPacketType(expected, found) => write!(
+                f,
+                "Wrong packet type, expected {} ({:#04X}), found {} ({:#04X})",
+                packet_type_name(*expected),
+                expected,
+                packet_type_name(*found),
+                found
+            ),
+            InvalidFirmwareVersion(found) => write!(
+                f,
+                "Invalid firmware version, expected 4 ASCII digits, found {:X?}",
+                found
+            ),
+            UnexpectedReservedByte { position, value } => write!(
+                f,
+                "Unexpected reserved byte at position {}, expected 0x00, found {:#04X}",
+                position, value
+            ),
+            NoSensorResponse => write!(f, "No sensor response, only 0xFF or 0x00 bytes read"),
+            ResyncFailed => write!(f, "No start byte found in the received bytes"),
+            InvalidHex { position } => write!(
+                f,
+                "Invalid hexadecimal packet, unexpected character at position {}",
+                position
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The command payload used to query the co2 cas concentration
+    /// from the device number 1 (the default device number)
+    ///
+    /// It is the same value as the result of
+    /// get_command_packet(Command::ReadGasConcentration, 1).
+    const READ_GAS_CONCENTRATION_COMMAND_ON_DEV1_PACKET: &[u8] =
+        &[0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
+    const _: () = assert!(READ_GAS_CONCENTRATION_COMMAND_ON_DEV1_PACKET[8] == 0x79);
+    static DISABLE_ABC_ON_DEV1_PACKET: Packet =
+        [0xFF, 0x01, 0x79, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86];
+    const _: () =
+        assert!(DISABLE_ABC_ON_DEV1_PACKET[8] == packet_checksum(&DISABLE_ABC_ON_DEV1_PACKET));
+
+    #[test]
+    fn test_packet_checksum() {
+        assert_eq!(
+            set_automatic_baseline_correction(1, false),
+            DISABLE_ABC_ON_DEV1_PACKET
+        );
+        assert_eq!(
+            0xD1,
+            packet_checksum(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn test_get_payload() {
+        assert_eq!(Err(MHZ19Error::WrongPacketLength(0)), parse_payload(&[]));
+        assert_eq!(Err(MHZ19Error::WrongPacketLength(1)), parse_payload(&[12]));
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(12)),
+            parse_payload(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12])
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongStartByte(10)),
+            parse_payload(&[10, 2, 3, 4, 5, 6, 7, 8, 9])
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongChecksum(221, 9)),
+            parse_payload(&[0xFF, 2, 3, 4, 5, 6, 7, 8, 9])
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongChecksum(0xD1, 0x10)),
+            parse_payload(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0x10])
+        );
+        assert_eq!(
+            Ok(&[0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00][..]),
+            parse_payload(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1])
+        );
+    }
+
+    #[test]
+    fn test_nonzero_fields() {
+        let packet = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        assert!(nonzero_fields(&packet).unwrap().eq([0, 1, 2, 3]));
+        assert_eq!(
+            Some(0),
+            nonzero_fields(&response(0x87, [0; 6])).unwrap().next()
+        );
+        assert!(nonzero_fields(&packet[..8]).is_err());
+    }
+
+    #[test]
+    fn test_no_sensor_response() {
+        assert_eq!(Err(MHZ19Error::NoSensorResponse), parse_payload(&[0xFF; 9]));
+        assert_eq!(Err(MHZ19Error::NoSensorResponse), parse_payload(&[0x00; 9]));
+        assert_eq!(
+            Err(MHZ19Error::NoSensorResponse),
+            parse_gas_concentration_ppm(&[0xFF; 9])
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(8)),
+            parse_payload(&[0xFF; 8])
+        );
+    }
+
+    #[test]
+    fn test_parse_hex_frame() {
+        assert_eq!(
+            Ok(read_gas_concentration(1).into()),
+            parse_hex_frame("FF 01 86 00 00 00 00 00 79")
+        );
+        assert_eq!(
+            Ok(read_gas_concentration(1).into()),
+            parse_hex_frame(" ff0186\t0000000000\n79 ")
+        );
+        assert_eq!(
+            Err(MHZ19Error::InvalidHex { position: 3 }),
+            parse_hex_frame("FF G1 86 00 00 00 00 00 79")
+        );
+        assert_eq!(
+            Err(MHZ19Error::InvalidHex { position: 27 }),
+            parse_hex_frame("FF 01 86 00 00 00 00 00 79 0")
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(10)),
+            parse_hex_frame("FF 01 86 00 00 00 00 00 79 00")
+        );
+        assert_eq!(Err(MHZ19Error::WrongPacketLength(0)), parse_hex_frame(""));
+        assert_eq!(
+            Err(MHZ19Error::WrongChecksum(0x79, 0x78)),
+            parse_hex_frame("FF 01 86 00 00 00 00 00 78")
+        );
+    }
+
+    #[test]
+    fn test_parse_payload_at() {
+        let mut buffer = [0xAAu8; 14];
+        buffer[3..12].copy_from_slice(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1]);
+        assert_eq!(
+            Ok(&[0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00][..]),
+            parse_payload_at(&buffer, 3)
+        );
+        assert_eq!(Ok(&buffer[4..11]), parse_payload_at(&buffer[..12], 3));
+        assert_eq!(
+            Err(MHZ19Error::WrongStartByte(0xAA)),
+            parse_payload_at(&buffer, 2)
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(8)),
+            parse_payload_at(&buffer, 6)
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(0)),
+            parse_payload_at(&buffer, 20)
+        );
+    }
+
+    #[test]
+    fn test_frames_equal_ignoring_checksum() {
+        let mut wrong_checksum: Packet = read_gas_concentration(1).into();
+        wrong_checksum[8] = 0x00;
+        assert!(frames_equal_ignoring_checksum(
+            &read_gas_concentration(1),
+            &wrong_checksum
+        ));
+        assert_ne!(read_gas_concentration(1), wrong_checksum);
+        assert!(!frames_equal_ignoring_checksum(
+            &read_gas_concentration(1),
+            &read_gas_concentration(2)
+        ));
+    }
+
+    #[test]
+    fn test_parse_payload_owned() {
+        let payload = {
+            let buffer = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+            parse_payload_owned(&buffer)
+        };
+        assert_eq!(
+            Ok(OwnedPayload {
+                command: 0x86,
+                data: [0x02, 0x60, 0x47, 0x00, 0x00, 0x00]
+            }),
+            payload
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(2)),
+            parse_payload_owned(&[0xFF, 0x86])
+        );
+    }
+
+    #[test]
+    fn test_byte_order() {
+        let little_endian = ParseOptions {
+            byte_order: ByteOrder::Little,
+            ..ParseOptions::default()
+        };
+        // 0x0260 = 608, 0x6002 = 24578
+        let packet = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        assert_eq!(
+            Ok(608),
+            parse_gas_concentration_ppm_with_options(&packet, &ParseOptions::default())
+        );
+        assert_eq!(
+            Ok(24578),
+            parse_gas_concentration_ppm_with_options(&packet, &little_endian)
+        );
+        let clone_packet = [0xFF, 0x86, 0x60, 0x02, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        assert_eq!(
+            Ok(608),
+            parse_gas_concentration_ppm_with_options(&clone_packet, &little_endian)
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketType(0x86, 0x87)),
+            parse_gas_concentration_ppm_with_options(&response(0x87, [0; 6]), &little_endian)
+        );
+        assert_eq!(0x1234, ByteOrder::Big.join_u16(0x12, 0x34));
+        assert_eq!(0x3412, ByteOrder::Little.join_u16(0x12, 0x34));
+    }
+
+    #[test]
+    fn test_parse_payload_with_options() {
+        let lenient = ParseOptions {
+            strip_trailing_crlf: true,
+            ..ParseOptions::default()
+        };
+        let payload = Ok(&[0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00][..]);
+        let frame = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        let frame_crlf = [
+            0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1, b'\r', b'\n',
+        ];
+
+        assert_eq!(payload, parse_payload_with_options(&frame, &lenient));
+        assert_eq!(payload, parse_payload_with_options(&frame_crlf, &lenient));
+        assert_eq!(
+            payload,
+            parse_payload_with_options(&frame_crlf[..10], &lenient)
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(11)),
+            parse_payload_with_options(&frame_crlf, &ParseOptions::default())
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(11)),
+            parse_payload(&frame_crlf)
+        );
+
+        // a checksum of 0x0A must not be stripped
+        let frame_lf_checksum = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0xC7, 0x0A, b'\n'];
+        assert_eq!(
+            Ok(&[0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0xC7][..]),
+            parse_payload_with_options(&frame_lf_checksum, &lenient)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_error_into_boxed_error() {
+        fn parse(packet: &[u8]) -> Result<u32, Box<dyn std::error::Error>> {
+            Ok(parse_gas_concentration_ppm(packet)?)
+        }
+        fn parse_send_sync(packet: &[u8]) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(parse_gas_concentration_ppm(packet)?)
+        }
+        let response = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+
+        assert_eq!(608, parse(&response).unwrap());
+        assert_eq!(608, parse_send_sync(&response).unwrap());
+        assert_eq!(
+            "Wrong packet length, expected 9, found 1",
+            parse(&[0xFF]).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "Wrong packet length, expected 9, found 1",
+            parse_send_sync(&[0xFF]).unwrap_err().to_string()
+        );
+    }
+
+    #[cfg(feature = "core-error")]
+    #[test]
+    fn test_core_error() {
+        fn describe(error: &dyn core::error::Error) -> bool {
+            error.source().is_none()
+        }
+        let error = parse_payload(&[0xFF]).unwrap_err();
+        assert!(describe(&error));
+    }
+
+    #[test]
+    fn test_parse_payload_strict() {
+        let packet = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        assert_eq!(parse_payload(&packet), parse_payload_strict(&packet));
+        // status byte is not reserved
+        let response_with_status = gas_concentration_response_with_status(0x40);
+        assert!(parse_payload_strict(&response_with_status).is_ok());
+        assert_eq!(
+            Err(MHZ19Error::UnexpectedReservedByte {
+                position: 7,
+                value: 0x12
+            }),
+            parse_payload_strict(&response(0x86, [0x02, 0x60, 0x47, 0x00, 0x00, 0x12]))
+        );
+        assert_eq!(
+            Err(MHZ19Error::UnexpectedReservedByte {
+                position: 5,
+                value: 0x01
+            }),
+            parse_payload_strict(&response(0x99, [0x00, 0x00, 0x00, 0x01, 0x00, 0x00]))
+        );
+        // unknown packet type
+        assert!(parse_payload_strict(&response(0x42, [1, 2, 3, 4, 5, 6])).is_ok());
+        assert_eq!(
+            Err(MHZ19Error::WrongChecksum(0xD1, 0x00)),
+            parse_payload_strict(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0x00])
+        );
+    }
+
+    #[test]
+    fn test_diagnose_frame() {
+        let packet = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        assert_eq!(FrameDiagnosis::Valid, diagnose_frame(&packet));
+        assert_eq!(
+            FrameDiagnosis::WrongLength { found: 8 },
+            diagnose_frame(&packet[..8])
+        );
+        assert_eq!(
+            FrameDiagnosis::WrongStartByte {
+                found: 0xD1,
+                start_byte_position: Some(1)
+            },
+            diagnose_frame(&[0xD1, 0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00])
+        );
+        assert_eq!(
+            FrameDiagnosis::WrongStartByte {
+                found: 0x00,
+                start_byte_position: None
+            },
+            diagnose_frame(&[0, 0, 0, 0, 0, 0, 0, 0, 1])
+        );
+        assert_eq!(FrameDiagnosis::NoSensorResponse, diagnose_frame(&[0; 9]));
+        assert_eq!(
+            FrameDiagnosis::WrongChecksum {
+                expected: 0xD1,
+                found: 0xD2
+            },
+            diagnose_frame(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD2])
+        );
+        assert_eq!(
+            FrameDiagnosis::UnknownPacketType(0x42),
+            diagnose_frame(&response(0x42, [0; 6]))
+        );
+        assert_eq!(
+            FrameDiagnosis::UnexpectedReservedByte {
+                position: 6,
+                value: 0x01
+            },
+            diagnose_frame(&response(0x86, [0x02, 0x60, 0x47, 0x00, 0x01, 0x00]))
+        );
+    }
+
+    #[test]
+    fn test_typed_packets() {
+        let command: CommandPacket = read_gas_concentration(1);
+        assert_eq!(
+            &[0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79][..],
+            command.as_ref()
         );
+        assert_eq!(0x86, command[2]);
+        let raw: Packet = command.into();
+        assert_eq!(command, CommandPacket::from(raw));
+
+        let bytes: &[u8] = &[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        let response = ResponsePacket::try_from(bytes).unwrap();
+        assert_eq!(Ok(608), parse_gas_concentration_ppm(response.as_ref()));
+        assert_eq!(Ok(608), parse_gas_concentration_ppm(&response));
         assert_eq!(
-            Err(MHZ19Error::WrongChecksum(0xD1, 0x10)),
-            parse_payload(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0x10])
+            response,
+            ResponsePacket::from([0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1])
         );
         assert_eq!(
-            Ok(&[0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00][..]),
-            parse_payload(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1])
+            Err(MHZ19Error::WrongPacketLength(3)),
+            ResponsePacket::try_from(&bytes[..3])
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_vec_conversions() {
+        use alloc::{vec, vec::Vec};
+
+        let bytes = vec![0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+        let response = ResponsePacket::try_from(bytes.clone()).unwrap();
+        assert_eq!(Ok(608), parse_gas_concentration_ppm(&response));
+        assert_eq!(bytes, Vec::from(response));
+
+        let mut too_long = bytes;
+        too_long.push(0x00);
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(10)),
+            ResponsePacket::try_from(too_long)
+        );
+
+        assert_eq!(
+            vec![0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79],
+            Vec::from(read_gas_concentration(1))
         );
     }
 
@@ -257,20 +2001,17 @@ mod test {
         );
         assert_eq!(
             Ok(&[0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00][..]),
-            parse_payload(&get_command_with_bytes34(
-                Command::ReadGasConcentration,
-                1,
-                0,
-                0
-            ))
+            parse_payload(
+                get_command_with_bytes34(Command::ReadGasConcentration, 1, 0, 0).as_ref()
+            )
         );
         assert_eq!(
             READ_GAS_CONCENTRATION_COMMAND_ON_DEV1_PACKET,
-            get_command_with_bytes34(Command::ReadGasConcentration, 1, 0, 0)
+            get_command_with_bytes34(Command::ReadGasConcentration, 1, 0, 0).as_ref()
         );
         assert_eq!(
             READ_GAS_CONCENTRATION_COMMAND_ON_DEV1_PACKET,
-            read_gas_concentration(1)
+            read_gas_concentration(1).as_ref()
         );
 
         // Check command values
@@ -292,6 +2033,675 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_command_values_are_unique() {
+        // Does not compile when a command is added: Command::ALL must be updated as well
+        for command in Command::ALL.iter() {
+            match command {
+                Command::ReadGasConcentration
+                | Command::CalibrateZero
+                | Command::CalibrateSpan
+                | Command::SetAutomaticBaselineCorrection
+                | Command::SetPersistentAutomaticBaselineCorrection
+                | Command::SetSensorDetectionRange
+                | Command::ReadAutomaticBaselineCorrection
+                | Command::ReadSensorDetectionRange
+                | Command::ReadFirmwareVersion
+                | Command::WriteEeprom
+                | Command::ReadEeprom => {}
+            }
+        }
+
+        for (i, command) in Command::ALL.iter().enumerate() {
+            for other in &Command::ALL[i + 1..] {
+                assert_ne!(
+                    command.get_command_value(),
+                    other.get_command_value(),
+                    "{:?} and {:?} have the same command value",
+                    command,
+                    other
+                );
+            }
+            assert_eq!(
+                Some(*command),
+                Command::from_command_value(command.get_command_value())
+            );
+        }
+    }
+
+    #[test]
+    fn test_packet_type_name() {
+        assert_eq!("ReadGasConcentration", packet_type_name(0x86));
+        assert_eq!("CalibrateZero", packet_type_name(0x87));
+        assert_eq!("CalibrateSpan", packet_type_name(0x88));
+        assert_eq!("SetAutomaticBaselineCorrection", packet_type_name(0x79));
+        assert_eq!(
+            "SetPersistentAutomaticBaselineCorrection",
+            packet_type_name(0x78)
+        );
+        assert_eq!("SetSensorDetectionRange", packet_type_name(0x99));
+        assert_eq!("ReadAutomaticBaselineCorrection", packet_type_name(0x7D));
+        assert_eq!("ReadSensorDetectionRange", packet_type_name(0x9B));
+        assert_eq!("ReadFirmwareVersion", packet_type_name(0xA0));
+        assert_eq!("WriteEeprom", packet_type_name(0xC4));
+        assert_eq!("ReadEeprom", packet_type_name(0xC5));
+        assert_eq!("Unknown", packet_type_name(0x00));
+        assert_eq!("Unknown", packet_type_name(0x12));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_wrong_packet_type_display() {
+        assert_eq!(
+            "Wrong packet type, expected ReadGasConcentration (0x86), found CalibrateZero (0x87)",
+            MHZ19Error::WrongPacketType(0x86, 0x87).to_string()
+        );
+        assert_eq!(
+            "Wrong packet type, expected ReadGasConcentration (0x86), found Unknown (0x0A)",
+            MHZ19Error::WrongPacketType(0x86, 0x0A).to_string()
+        );
+    }
+
+    #[test]
+    fn test_build_gas_concentration_response() {
+        assert_eq!(
+            [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1],
+            build_gas_concentration_response(608, 31, 0)
+        );
+        for (ppm, temperature_celsius, status) in [(0, -40, 0x40), (65535, 215, 0xFF), (412, 24, 0)]
+        {
+            assert_eq!(
+                Ok(GasReading {
+                    co2_ppm: ppm,
+                    temperature_celsius,
+                    status
+                }),
+                parse_gas_reading(&build_gas_concentration_response(
+                    ppm,
+                    temperature_celsius,
+                    status
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_firmware_version_ord() {
+        let version = |major, minor, patch| FirmwareVersion {
+            major,
+            minor,
+            patch,
+        };
+        assert!(version(4, 43, 0) > version(4, 42, 9));
+        assert!(version(5, 0, 0) > version(4, 43, 0));
+        assert!(version(4, 43, 1) > version(4, 43, 0));
+        assert_eq!(
+            Some(version(4, 43, 0)),
+            FirmwareVersion::parse_from_bytes(&[0xA0, b'0', b'4', b'4', b'3', 0, 0])
+        );
+        assert_eq!(
+            None,
+            FirmwareVersion::parse_from_bytes(&[0xA0, 0x04, 0x2B, 0x00, 0x00, 0, 0])
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_firmware_version_display() {
+        assert_eq!(
+            "v4.43.0",
+            FirmwareVersion {
+                major: 4,
+                minor: 43,
+                patch: 0
+            }
+            .to_string()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_detection_range_display() {
+        assert_eq!("2000 ppm", DetectionRange::Ppm2000.to_string());
+        assert_eq!("5000 ppm", DetectionRange::Ppm5000.to_string());
+        assert_eq!("10000 ppm", DetectionRange::Ppm10000.to_string());
+    }
+
+    /// Build a valid "read gas concentration" response with the given status byte
+    fn gas_concentration_response_with_status(status: u8) -> Packet {
+        let mut packet = [0xFF, 0x86, 0x02, 0x60, 0x47, status, 0x00, 0x00, 0x00];
+        packet[8] = checksum(&packet[1..8]);
+        packet
+    }
+
+    #[test]
+    fn test_parse_status_flags() {
+        assert_eq!(
+            Ok(StatusFlags {
+                valid: true,
+                warmup: false,
+                fault: false
+            }),
+            parse_status_flags(&gas_concentration_response_with_status(0x40))
+        );
+        assert_eq!(
+            Ok(StatusFlags {
+                valid: false,
+                warmup: true,
+                fault: false
+            }),
+            parse_status_flags(&gas_concentration_response_with_status(0x10))
+        );
+        assert_eq!(
+            Ok(StatusFlags {
+                valid: false,
+                warmup: false,
+                fault: true
+            }),
+            parse_status_flags(&gas_concentration_response_with_status(0x80))
+        );
+        assert_eq!(
+            Ok(StatusFlags {
+                valid: true,
+                warmup: true,
+                fault: true
+            }),
+            parse_status_flags(&gas_concentration_response_with_status(0xFF))
+        );
+        assert_eq!(
+            Ok(StatusFlags {
+                valid: false,
+                warmup: false,
+                fault: false
+            }),
+            parse_status_flags(&gas_concentration_response_with_status(0x00))
+        );
+
+        let masks = StatusMasks {
+            valid: 0x01,
+            warmup: 0x02,
+            fault: 0x04,
+        };
+        assert_eq!(
+            Ok(StatusFlags {
+                valid: true,
+                warmup: false,
+                fault: true
+            }),
+            parse_status_flags_with_masks(&gas_concentration_response_with_status(0x05), &masks)
+        );
+
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketType(0x86, 0x01)),
+            parse_status_flags(read_gas_concentration(1).as_ref())
+        );
+    }
+
+    /// Build a valid response packet for the given command byte & payload data
+    fn response(command: u8, data: [u8; 6]) -> Packet {
+        let mut packet = [
+            0xFF, command, data[0], data[1], data[2], data[3], data[4], data[5], 0,
+        ];
+        packet[8] = checksum(&packet[1..8]);
+        packet
+    }
+
+    #[test]
+    fn test_parse_gas_reading() {
+        assert_eq!(
+            Ok(GasReading {
+                co2_ppm: 608,
+                temperature_celsius: 31,
+                status: 0x00
+            }),
+            parse_gas_reading(&[0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1])
+        );
+        assert_eq!(
+            Ok(GasReading {
+                co2_ppm: 412,
+                temperature_celsius: -2,
+                status: 0x40
+            }),
+            parse_gas_reading(&response(0x86, [0x01, 0x9C, 0x26, 0x40, 0x00, 0x00]))
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketType(0x86, 0x7D)),
+            parse_gas_reading(&response(0x7D, [0; 6]))
+        );
+    }
+
+    #[test]
+    fn test_read_commands() {
+        assert_eq!(
+            [0xFF, 0x01, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x82],
+            read_abc_status(1)
+        );
+        assert_eq!(
+            [0xFF, 0x01, 0x9B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64],
+            read_detection_range(1)
+        );
+        assert_eq!(
+            [0xFF, 0x01, 0xA0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5F],
+            read_firmware_version(1)
+        );
+    }
+
+    #[test]
+    fn test_parse_read_responses() {
+        assert_eq!(
+            Ok(true),
+            parse_abc_status(&response(0x7D, [0, 0, 0, 0, 0, 1]))
+        );
+        assert_eq!(
+            Ok(false),
+            parse_abc_status(&response(0x7D, [0, 0, 0, 0, 0, 0]))
+        );
+        assert_eq!(
+            Ok(5000),
+            parse_detection_range(&response(0x9B, [0, 0, 0x13, 0x88, 0, 0]))
+        );
+        assert_eq!(
+            Ok(FirmwareVersion {
+                major: 4,
+                minor: 43,
+                patch: 0,
+            }),
+            parse_firmware_version(&response(0xA0, [b'0', b'4', b'4', b'3', 0, 0]))
+        );
+        assert_eq!(
+            Err(MHZ19Error::InvalidFirmwareVersion([b'0', b'4', b'.', b'3'])),
+            parse_firmware_version(&response(0xA0, [b'0', b'4', b'.', b'3', 0, 0]))
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketType(0x9B, 0x86)),
+            parse_detection_range(&response(0x86, [0; 6]))
+        );
+        assert_eq!(
+            Some(DetectionRange::Ppm2000),
+            DetectionRange::from_ppm(2000)
+        );
+        assert_eq!(
+            Some(DetectionRange::Ppm5000),
+            DetectionRange::from_ppm(5000)
+        );
+        assert_eq!(
+            Some(DetectionRange::Ppm10000),
+            DetectionRange::from_ppm(10000)
+        );
+        assert_eq!(None, DetectionRange::from_ppm(3000));
+        for range in [
+            DetectionRange::Ppm2000,
+            DetectionRange::Ppm5000,
+            DetectionRange::Ppm10000,
+        ] {
+            assert_eq!(Some(range), DetectionRange::from_ppm(range.value()));
+        }
+        // 10000 ppm sensors readings
+        assert_eq!(
+            Ok(10000),
+            parse_gas_concentration_ppm(&response(0x86, [0x27, 0x10, 0x47, 0x00, 0x00, 0x00]))
+        );
+    }
+
+    #[test]
+    fn test_recommended_wait() {
+        use core::time::Duration;
+        assert_eq!(
+            Duration::from_secs(1200),
+            recommended_wait(StabilisationCause::ZeroCalibration)
+        );
+        assert_eq!(
+            Duration::from_secs(1200),
+            recommended_wait(StabilisationCause::SpanCalibration)
+        );
+        assert_eq!(
+            Duration::from_secs(86400),
+            recommended_wait(StabilisationCause::EnableAutomaticBaselineCorrection)
+        );
+    }
+
+    #[test]
+    fn test_sensor_duration() {
+        use core::time::Duration;
+        assert_eq!(
+            Duration::from_secs(180),
+            SensorDuration::WARMUP.as_duration()
+        );
+        assert_eq!(
+            Duration::from_secs(1),
+            Duration::from(SensorDuration::COMMAND_RESPONSE_TIMEOUT)
+        );
+        assert!(SensorDuration::WARMUP < SensorDuration::CALIBRATION_STABILISATION);
+    }
+
+    #[test]
+    fn test_set_abc_persistent() {
+        assert_eq!(
+            [0xFF, 0x01, 0x78, 0xA0, 0x00, 0x00, 0x00, 0x00, 0xE7],
+            set_abc_persistent(1, true)
+        );
+        assert_eq!(
+            [0xFF, 0x01, 0x78, 0x00, 0x00, 0x00, 0x00, 0x00, 0x87],
+            set_abc_persistent(1, false)
+        );
+        assert_ne!(
+            set_automatic_baseline_correction(1, false)[2],
+            set_abc_persistent(1, false)[2]
+        );
+    }
+
+    #[test]
+    fn test_eeprom() {
+        assert_eq!(
+            [0xFF, 0x01, 0xC4, 0x10, 0x12, 0x34, 0x00, 0x00, 0xE5],
+            write_eeprom(1, 0x10, 0x1234)
+        );
+        assert_eq!(
+            [0xFF, 0x01, 0xC5, 0x10, 0x00, 0x00, 0x00, 0x00, 0x2A],
+            read_eeprom(1, 0x10)
+        );
+        assert_eq!(
+            Ok(0x1234),
+            parse_eeprom_response(&response(0xC5, [0x10, 0x12, 0x34, 0x00, 0x00, 0x00]))
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketType(0xC5, 0xC4)),
+            parse_eeprom_response(&response(0xC4, [0x10, 0x12, 0x34, 0x00, 0x00, 0x00]))
+        );
+    }
+
+    #[test]
+    fn test_parse_calibration_result() {
+        assert_eq!(
+            Ok(CalibrationOutcome::Accepted),
+            parse_calibration_result(&response(0x87, [0x00; 6]))
+        );
+        assert_eq!(
+            Ok(CalibrationOutcome::Rejected(0x01)),
+            parse_calibration_result(&response(0x87, [0x01, 0x00, 0x00, 0x00, 0x00, 0x00]))
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketType(0x87, 0x88)),
+            parse_calibration_result(&response(0x88, [0x00; 6]))
+        );
+    }
+
+    #[test]
+    fn test_read_gas_concentration_broadcast() {
+        assert_eq!(
+            [0xFF, 0x00, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7A],
+            read_gas_concentration_broadcast()
+        );
+        assert_eq!(
+            read_gas_concentration(0),
+            read_gas_concentration_broadcast()
+        );
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let command = set_detection_range(1, 2000);
+        let mut packets = broadcast(&command, &[1, 2, 3]);
+        for device_number in 1..=3 {
+            let packet = packets.next().unwrap();
+            assert_eq!(set_detection_range(device_number, 2000), packet);
+            assert_eq!(device_number, packet[1]);
+            assert!(parse_payload(packet.as_ref()).is_ok());
+        }
+        assert_eq!(None, packets.next());
+        assert_eq!(0, broadcast(&command, &[]).count());
+    }
+
+    #[test]
+    fn test_set_abc_interval() {
+        assert_eq!(
+            [0xFF, 0x01, 0x79, 0xA0, 0x18, 0x00, 0x00, 0x00, 0xCE],
+            set_abc_interval(1, AbcInterval::DEFAULT)
+        );
+        assert_eq!(
+            [0xFF, 0x01, 0x79, 0xA0, 0x30, 0x00, 0x00, 0x00, 0xB6],
+            set_abc_interval(1, AbcInterval { hours: 48 })
+        );
+        assert_eq!(
+            set_automatic_baseline_correction(1, false),
+            set_abc_interval(1, AbcInterval::DISABLED)
+        );
+        assert!(parse_payload(set_abc_interval(2, AbcInterval { hours: 12 }).as_ref()).is_ok());
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(1, Sensor::default().device_number);
+        assert_eq!(DEFAULT_DEVICE_NUMBER, Sensor::default().device_number);
+        assert_eq!(
+            read_gas_concentration(1),
+            Sensor::default().read_gas_concentration()
+        );
+        assert_eq!(
+            SensorConfig {
+                device_number: 1,
+                model: SensorModel::MHZ19B,
+                detection_range: DetectionRange::Ppm5000,
+                abc_enabled: true
+            },
+            SensorConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_sensor_builder() {
+        assert_eq!(SensorConfig::default(), SensorBuilder::new().build());
+        let config = SensorBuilder::new()
+            .with_device_number(4)
+            .with_model(SensorModel::MHZ14)
+            .with_detection_range(DetectionRange::Ppm2000)
+            .with_abc(false)
+            .build();
+        assert_eq!(
+            SensorConfig {
+                device_number: 4,
+                model: SensorModel::MHZ14,
+                detection_range: DetectionRange::Ppm2000,
+                abc_enabled: false
+            },
+            config
+        );
+        assert_eq!(Sensor::new(4), config.sensor());
+        assert_eq!(read_gas_concentration(4), config.read_gas_concentration());
+        assert_eq!(
+            calibrate_zero_point(4),
+            config.calibrate_zero_point(ZeroCalibrationPoint::STANDARD)
+        );
+        assert_eq!(
+            calibrate_span_point(4, 2000),
+            config.calibrate_span_point(SpanCalibrationPoint::RECOMMENDED)
+        );
+        assert_eq!(
+            [
+                set_detection_range(4, 2000),
+                set_automatic_baseline_correction(4, false)
+            ],
+            config.apply_commands()
+        );
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let current = SensorConfig {
+            device_number: 2,
+            ..SensorConfig::default()
+        };
+        let desired = SensorConfig {
+            detection_range: DetectionRange::Ppm2000,
+            ..SensorConfig::default()
+        };
+        let mut diff = config_diff(&current, &desired);
+        assert_eq!(Some(set_detection_range(2, 2000)), diff.next());
+        assert_eq!(None, diff.next());
+
+        let desired = SensorConfig {
+            abc_enabled: false,
+            ..desired
+        };
+        assert!(config_diff(&current, &desired).eq([
+            set_detection_range(2, 2000),
+            set_automatic_baseline_correction(2, false)
+        ]));
+        assert_eq!(None, config_diff(&current, &current).next());
+    }
+
+    #[test]
+    fn test_sensor_commands() {
+        let sensor = Sensor::new(3);
+        assert_eq!(read_gas_concentration(3), sensor.read_gas_concentration());
+        assert_eq!(
+            set_automatic_baseline_correction(3, false),
+            sensor.set_automatic_baseline_correction(false)
+        );
+        assert_eq!(
+            set_detection_range(3, 2000),
+            sensor.set_detection_range(DetectionRange::Ppm2000)
+        );
+        assert_eq!(
+            set_detection_range(3, 5000),
+            sensor.set_detection_range(DetectionRange::Ppm5000)
+        );
+        assert_eq!(
+            [0xFF, 0x03, 0x99, 0x27, 0x10, 0x00, 0x00, 0x00, 0x2D],
+            sensor.set_detection_range(DetectionRange::Ppm10000)
+        );
+        assert_eq!(
+            calibrate_zero_point(3),
+            sensor.calibrate_zero_point(ZeroCalibrationPoint::new(405))
+        );
+        assert_eq!(
+            calibrate_span_point(3, 2000),
+            sensor.calibrate_span_point(SpanCalibrationPoint::new(2000).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_calibration_points() {
+        assert_eq!(400, ZeroCalibrationPoint::STANDARD.reference_ppm);
+        assert_eq!(410, ZeroCalibrationPoint::new(410).reference_ppm);
+        assert_eq!(None, SpanValue::new(999));
+        assert_eq!(Some(SpanValue::MIN), SpanValue::new(1000));
+        assert_eq!(
+            Some(SpanCalibrationPoint::RECOMMENDED),
+            SpanCalibrationPoint::new(2000)
+        );
+        assert_eq!(None, SpanCalibrationPoint::new(400));
+        assert_eq!(
+            calibrate_span_point(1, 2500),
+            calibrate_span_reference(1, SpanCalibrationPoint::new(2500).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_calibrate_span_point_encodes_all_values() {
+        // exhaustive: guards against operator precedence regressions in the encoding (#3)
+        for value in 0..=u16::MAX {
+            let packet = calibrate_span_point(1, value);
+            assert_eq!(value, ((packet[3] as u16) << 8) | (packet[4] as u16));
+            assert_eq!(Ok(&packet[1..8]), parse_payload(packet.as_ref()));
+        }
+    }
+
+    #[test]
+    fn test_eq_impls() {
+        fn is_eq<T: Eq>() {}
+        is_eq::<MHZ19Error>();
+        is_eq::<GasReading>();
+        is_eq::<DetectionRange>();
+        is_eq::<FirmwareVersion>();
+        is_eq::<FrameDiagnosis>();
+        is_eq::<CommandPacket>();
+        is_eq::<ResponsePacket>();
+    }
+
+    #[test]
+    fn test_diff_readings() {
+        let reading = |co2_ppm, temperature_celsius| GasReading {
+            co2_ppm,
+            temperature_celsius,
+            status: 0,
+        };
+        let diff = diff_readings(&reading(400, 20), &reading(700, 22), 90);
+        assert_eq!(
+            GasReadingDiff {
+                ppm_delta: 300,
+                temperature_delta_celsius: 2,
+                elapsed_seconds: 90
+            },
+            diff
+        );
+        assert!(diff.is_increasing());
+        assert!(!diff.is_stable(299));
+        assert!(diff.is_stable(300));
+        assert_eq!(200.0, diff.rate_ppm_per_minute());
+
+        let diff = diff_readings(&reading(700, 22), &reading(400, 20), 0);
+        assert_eq!(-300, diff.ppm_delta);
+        assert_eq!(-2, diff.temperature_delta_celsius);
+        assert!(!diff.is_increasing());
+        assert!(diff.is_stable(300));
+        assert_eq!(0.0, diff.rate_ppm_per_minute());
+
+        let diff = diff_readings(&reading(u32::MAX, i16::MIN), &reading(0, i16::MAX), 60);
+        assert_eq!(i32::MIN, diff.ppm_delta);
+        assert_eq!(i16::MAX, diff.temperature_delta_celsius);
+    }
+
+    #[test]
+    fn test_ppm_delta() {
+        assert_eq!(200, ppm_delta(400, 600));
+        assert_eq!(-200, ppm_delta(600, 400));
+        assert_eq!(0, ppm_delta(600, 600));
+        assert_eq!(i32::MAX, ppm_delta(0, u32::MAX));
+        assert_eq!(-i32::MAX, ppm_delta(i32::MAX as u32, 0));
+        assert_eq!(i32::MIN, ppm_delta(u32::MAX, 0));
+
+        assert_eq!(Some(100.0), ppm_rate_per_minute(400, 600, 120_000));
+        assert_eq!(Some(-400.0), ppm_rate_per_minute(600, 400, 30_000));
+        assert_eq!(Some(0.0), ppm_rate_per_minute(600, 600, 1000));
+        assert_eq!(None, ppm_rate_per_minute(400, 600, 0));
+    }
+
+    #[test]
+    fn test_ppm_range() {
+        let range = PpmRange::new(400, 1000);
+        assert_eq!(PpmRange::SAFE, range);
+        assert!(range.contains(400));
+        assert!(range.contains(1000));
+        assert!(!range.contains(399));
+        assert!(!range.contains(1001));
+        assert_eq!(400, range.clamp(0));
+        assert_eq!(600, range.clamp(600));
+        assert_eq!(1000, range.clamp(5000));
+        assert!(range.overlaps(&PpmRange::new(1000, 2000)));
+        assert!(range.overlaps(&PpmRange::new(500, 600)));
+        assert!(range.overlaps(&PpmRange::SENSOR));
+        assert!(!range.overlaps(&PpmRange::new(1001, 2000)));
+        assert!(!range.overlaps(&PpmRange::new(0, 399)));
+        assert!(PpmRange::FULL.contains(u32::MAX));
+    }
+
+    #[test]
+    fn test_split_join_u16_be() {
+        assert_eq!((0x07, 0xD0), split_u16_be(0x07D0));
+        assert_eq!((0x13, 0x88), split_u16_be(5000));
+        assert_eq!((0x00, 0x00), split_u16_be(0));
+        assert_eq!((0x00, 0xFF), split_u16_be(0x00FF));
+        assert_eq!((0xFF, 0x00), split_u16_be(0xFF00));
+        assert_eq!((0xFF, 0xFF), split_u16_be(0xFFFF));
+        assert_eq!(0x07D0, join_u16_be(0x07, 0xD0));
+        assert_eq!(0xFF00, join_u16_be(0xFF, 0x00));
+        for value in 0..=u16::MAX {
+            let (high, low) = split_u16_be(value);
+            assert_eq!(value, join_u16_be(high, low));
+        }
+    }
+
     #[test]
     fn issue_3_op_precedence() {
         let p = set_detection_range(1, 0x07D0);
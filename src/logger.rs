@@ -0,0 +1,247 @@
+//! Buffering of readings in RAM for data loggers, flushed periodically as CSV.
+
+use crate::fmt::SliceWriter;
+use crate::GasReading;
+use core::fmt::Write;
+
+/// A reading with the time it was taken, in a unit chosen by the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampedReading<T> {
+    pub timestamp: T,
+    pub reading: GasReading,
+}
+
+/// Longest CSV row of a [`TimestampedReading<u64>`]
+const MAX_CSV_ROW_LEN: usize = 64;
+
+impl TimestampedReading<u64> {
+    /// Format the reading as a CSV row in the given buffer:
+    /// `timestamp,co2_ppm,temperature_celsius,status\n`.
+    fn csv_row<'a>(&self, buffer: &'a mut [u8; MAX_CSV_ROW_LEN]) -> &'a [u8] {
+        let mut writer = SliceWriter::new(buffer);
+        // cannot fail: the buffer holds the longest row
+        let _ = writeln!(
+            writer,
+            "{},{},{},{}",
+            self.timestamp,
+            self.reading.co2_ppm,
+            self.reading.temperature_celsius,
+            self.reading.status
+        );
+        writer.into_slice()
+    }
+}
+
+/// Behavior of a [`TimeSeriesBuffer`] receiving a reading while full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The oldest reading is dropped
+    #[default]
+    OverwriteOldest,
+    /// The new reading is given back to the caller
+    Reject,
+}
+
+/// Fixed capacity buffer of the last `N` readings, allocated on the stack.
+#[derive(Debug, Clone)]
+pub struct TimeSeriesBuffer<const N: usize> {
+    entries: [Option<TimestampedReading<u64>>; N],
+    write_index: usize,
+    count: usize,
+    policy: OverflowPolicy,
+}
+
+impl<const N: usize> Default for TimeSeriesBuffer<N> {
+    fn default() -> Self {
+        Self::new(OverflowPolicy::default())
+    }
+}
+
+impl<const N: usize> TimeSeriesBuffer<N> {
+    pub fn new(policy: OverflowPolicy) -> Self {
+        TimeSeriesBuffer {
+            entries: [None; N],
+            write_index: 0,
+            count: 0,
+            policy,
+        }
+    }
+
+    /// Add a reading, applying the overflow policy if the buffer is full.
+    ///
+    /// Returns the reading back if it was rejected.
+    pub fn push(
+        &mut self,
+        reading: TimestampedReading<u64>,
+    ) -> Result<(), TimestampedReading<u64>> {
+        if N == 0 || (self.is_full() && self.policy == OverflowPolicy::Reject) {
+            return Err(reading);
+        }
+        self.entries[self.write_index] = Some(reading);
+        self.write_index = (self.write_index + 1) % N;
+        self.count = (self.count + 1).min(N);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.count == N
+    }
+
+    /// Iterate over the buffered readings, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TimestampedReading<u64>> {
+        let oldest = self.oldest_index();
+        (0..self.count).filter_map(move |i| self.entries[(oldest + i) % N].as_ref())
+    }
+
+    fn oldest_index(&self) -> usize {
+        if N == 0 {
+            0
+        } else {
+            (self.write_index + N - self.count) % N
+        }
+    }
+
+    fn oldest(&self) -> Option<TimestampedReading<u64>> {
+        self.iter().next().copied()
+    }
+
+    /// Remove the oldest reading.
+    fn pop_oldest(&mut self) {
+        let oldest = self.oldest_index();
+        self.entries[oldest] = None;
+        self.count -= 1;
+    }
+
+    /// Write the buffered readings as CSV rows (see [`Self::flush_csv`]) in the given buffer,
+    /// returning the number of bytes written.
+    ///
+    /// Only the readings whose row fits entirely in the buffer are written and removed, the
+    /// others are kept for the next flush.
+    pub fn flush_csv_into(&mut self, buffer: &mut [u8]) -> usize {
+        let mut written = 0;
+        while let Some(entry) = self.oldest() {
+            let mut row = [0u8; MAX_CSV_ROW_LEN];
+            let row = entry.csv_row(&mut row);
+            let end = written + row.len();
+            if end > buffer.len() {
+                break;
+            }
+            buffer[written..end].copy_from_slice(row);
+            written = end;
+            self.pop_oldest();
+        }
+        written
+    }
+
+    /// Write the buffered readings, oldest first, as CSV rows without header:
+    /// `timestamp,co2_ppm,temperature_celsius,status`.
+    ///
+    /// Returns the number of readings written, which are removed from the buffer. On error, the
+    /// readings not written yet are kept.
+    #[cfg(feature = "std")]
+    pub fn flush_csv(&mut self, writer: &mut dyn std::io::Write) -> std::io::Result<usize> {
+        let mut flushed = 0;
+        while let Some(entry) = self.oldest() {
+            writer.write_all(entry.csv_row(&mut [0u8; MAX_CSV_ROW_LEN]))?;
+            self.pop_oldest();
+            flushed += 1;
+        }
+        Ok(flushed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reading(timestamp: u64, co2_ppm: u32) -> TimestampedReading<u64> {
+        TimestampedReading {
+            timestamp,
+            reading: GasReading {
+                co2_ppm,
+                temperature_celsius: 24,
+                status: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut buffer = TimeSeriesBuffer::<3>::default();
+        assert!(buffer.is_empty());
+        for i in 0..5 {
+            assert_eq!(Ok(()), buffer.push(reading(i, 400 + i as u32)));
+        }
+        assert!(buffer.is_full());
+        assert_eq!(
+            [2, 3, 4],
+            [0, 1, 2].map(|i| buffer.iter().nth(i).unwrap().timestamp)
+        );
+    }
+
+    #[test]
+    fn test_reject() {
+        let mut buffer = TimeSeriesBuffer::<2>::new(OverflowPolicy::Reject);
+        assert_eq!(Ok(()), buffer.push(reading(0, 400)));
+        assert_eq!(Ok(()), buffer.push(reading(1, 410)));
+        assert_eq!(Err(reading(2, 420)), buffer.push(reading(2, 420)));
+        assert_eq!(2, buffer.len());
+        assert_eq!(0, buffer.iter().next().unwrap().timestamp);
+
+        let mut empty = TimeSeriesBuffer::<0>::default();
+        assert_eq!(Err(reading(0, 400)), empty.push(reading(0, 400)));
+    }
+
+    #[test]
+    fn test_flush_csv_into() {
+        let mut buffer = TimeSeriesBuffer::<4>::default();
+        for i in 0..3 {
+            buffer.push(reading(1000 + i, 400 + i as u32)).unwrap();
+        }
+        let mut longest = [0u8; MAX_CSV_ROW_LEN];
+        let longest = TimestampedReading {
+            timestamp: u64::MAX,
+            reading: GasReading {
+                co2_ppm: u32::MAX,
+                temperature_celsius: i16::MIN,
+                status: u8::MAX,
+            },
+        }
+        .csv_row(&mut longest);
+        assert_eq!(b'\n', longest[longest.len() - 1]);
+
+        let mut out = [0u8; 30];
+        let len = buffer.flush_csv_into(&mut out);
+        assert_eq!(&b"1000,400,24,0\n1001,401,24,0\n"[..], &out[..len]);
+        assert_eq!(1, buffer.len());
+
+        let len = buffer.flush_csv_into(&mut out);
+        assert_eq!(&b"1002,402,24,0\n"[..], &out[..len]);
+        assert!(buffer.is_empty());
+        assert_eq!(0, buffer.flush_csv_into(&mut out));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_flush_csv() {
+        let mut buffer = TimeSeriesBuffer::<4>::default();
+        for i in 0..5 {
+            buffer.push(reading(i, 400 + i as u32)).unwrap();
+        }
+        let mut out = Vec::new();
+        assert_eq!(4, buffer.flush_csv(&mut out).unwrap());
+        assert_eq!(
+            "1,401,24,0\n2,402,24,0\n3,403,24,0\n4,404,24,0\n",
+            String::from_utf8(out).unwrap()
+        );
+        assert!(buffer.is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! NMEA 0183 style sentences, to feed readings to marine electronics (chart plotters, ...).
+//!
+//! The sentences are not standard NMEA sentences: `$<talker id>CO2,<ppm>,<temperature>*<checksum>`.
+
+use crate::fmt::SliceWriter;
+use crate::GasReading;
+use core::fmt::{self, Write};
+
+/// Buffer size always large enough for a sentence written by [`write_nmea_sentence`].
+pub const MAX_SENTENCE_LEN: usize = 32;
+
+/// NMEA checksum of the characters between `$` and `*`: all the bytes XOR-ed.
+pub fn nmea_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |checksum, byte| checksum ^ byte)
+}
+
+/// Write a reading as a sentence like `$IICO2,608,31.0*1C\r\n` in the given buffer, returning
+/// the written sentence.
+///
+/// Fails if the talker id is not made of 2 uppercase ascii letters, or if the buffer is too small
+/// (see [`MAX_SENTENCE_LEN`]).
+pub fn write_nmea_sentence<'a>(
+    reading: &GasReading,
+    talker_id: &str,
+    buffer: &'a mut [u8],
+) -> Result<&'a str, fmt::Error> {
+    if talker_id.len() != 2 || !talker_id.bytes().all(|b| b.is_ascii_uppercase()) {
+        return Err(fmt::Error);
+    }
+    let mut writer = SliceWriter::new(buffer);
+    write!(
+        writer,
+        "${}CO2,{},{}.0",
+        talker_id, reading.co2_ppm, reading.temperature_celsius
+    )?;
+    let checksum = nmea_checksum(&writer.written()[1..]);
+    write!(writer, "*{:02X}\r\n", checksum)?;
+    Ok(writer.into_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nmea_checksum() {
+        assert_eq!(
+            0x28,
+            nmea_checksum(b"GPGLL,5300.97914,N,00259.98174,E,125926,A")
+        );
+        assert_eq!(
+            0x76,
+            nmea_checksum(b"GPGGA,092750.000,5321.6802,N,00630.3372,W,1,8,1.03,61.7,M,55.2,M,,")
+        );
+        assert_eq!(0, nmea_checksum(b""));
+    }
+
+    #[test]
+    fn test_write_nmea_sentence() {
+        let mut buffer = [0u8; MAX_SENTENCE_LEN];
+        let reading = GasReading {
+            co2_ppm: 608,
+            temperature_celsius: 31,
+            status: 0,
+        };
+        assert_eq!(
+            Ok("$IICO2,608,31.0*1C\r\n"),
+            write_nmea_sentence(&reading, "II", &mut buffer)
+        );
+        let reading = GasReading {
+            co2_ppm: 412,
+            temperature_celsius: -5,
+            status: 0,
+        };
+        assert_eq!(
+            Ok("$IICO2,412,-5.0*0F\r\n"),
+            write_nmea_sentence(&reading, "II", &mut buffer)
+        );
+        let reading = GasReading {
+            co2_ppm: u32::MAX,
+            temperature_celsius: i16::MIN,
+            status: 0,
+        };
+        assert!(write_nmea_sentence(&reading, "II", &mut buffer).is_ok());
+
+        assert_eq!(
+            Err(fmt::Error),
+            write_nmea_sentence(&reading, "II", &mut [0u8; 10])
+        );
+        assert_eq!(
+            Err(fmt::Error),
+            write_nmea_sentence(&reading, "ii", &mut buffer)
+        );
+        assert_eq!(
+            Err(fmt::Error),
+            write_nmea_sentence(&reading, "IIX", &mut buffer)
+        );
+    }
+}
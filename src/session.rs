@@ -0,0 +1,234 @@
+//! Analysis of captured sessions: logs of the commands sent to a sensor, each followed by the
+//! response of the sensor.
+
+use crate::{
+    parse_abc_status, parse_detection_range, parse_eeprom_response, parse_firmware_version,
+    parse_gas_reading, parse_payload, Command, CommandPacket, FirmwareVersion, GasReading,
+    MHZ19Error, Packet, ResponsePacket,
+};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// A decoded response packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    GasReading(GasReading),
+    AbcStatus(bool),
+    DetectionRange(u16),
+    FirmwareVersion(FirmwareVersion),
+    Eeprom(u16),
+    /// Response without value (eg to a setting command) or of an unknown type
+    Other(ResponsePacket),
+}
+
+/// Decode a response packet according to its type.
+pub fn decode_response(packet: &[u8]) -> Result<Response, MHZ19Error> {
+    let payload = parse_payload(packet)?;
+    use Command::*;
+    Ok(match Command::from_command_value(payload[0]) {
+        Some(ReadGasConcentration) => Response::GasReading(parse_gas_reading(packet)?),
+        Some(ReadAutomaticBaselineCorrection) => Response::AbcStatus(parse_abc_status(packet)?),
+        Some(ReadSensorDetectionRange) => Response::DetectionRange(parse_detection_range(packet)?),
+        Some(ReadFirmwareVersion) => Response::FirmwareVersion(parse_firmware_version(packet)?),
+        Some(ReadEeprom) => Response::Eeprom(parse_eeprom_response(packet)?),
+        Some(CalibrateZero)
+        | Some(CalibrateSpan)
+        | Some(SetAutomaticBaselineCorrection)
+        | Some(SetPersistentAutomaticBaselineCorrection)
+        | Some(SetSensorDetectionRange)
+        | Some(WriteEeprom)
+        | None => Response::Other(ResponsePacket::try_from(packet)?),
+    })
+}
+
+/// A command and the response following it in a session log.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Exchange {
+    pub command: CommandPacket,
+    /// `None` if the log ends with the command or if the next frame is a command
+    pub response: Option<Result<Response, MHZ19Error>>,
+}
+
+/// Iterator over the exchanges of a log made of 9 bytes frames, alternately a command and its
+/// response.
+///
+/// An invalid command frame is reported as an error, the next frame being read as the
+/// next command. A frame looking like a command (its 3rd byte being a known command, and not
+/// its 2nd) is not taken as a response: the exchange has no response and the frame is read as
+/// the next command, so a response missing from the log does not shift the next exchanges.
+/// The bytes of a trailing incomplete frame are ignored.
+#[derive(Debug, Clone)]
+pub struct SessionReader<'a> {
+    frames: core::slice::ChunksExact<'a, u8>,
+}
+
+impl<'a> SessionReader<'a> {
+    pub fn new(log: &'a [u8]) -> Self {
+        SessionReader {
+            frames: log.chunks_exact(9),
+        }
+    }
+}
+
+impl<'a> Iterator for SessionReader<'a> {
+    type Item = Result<Exchange, MHZ19Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.frames.next()?;
+        if let Err(e) = parse_payload(frame) {
+            return Some(Err(e));
+        }
+        let mut packet: Packet = [0; 9];
+        packet.copy_from_slice(frame);
+        let command = CommandPacket::from(packet);
+        let response = self
+            .frames
+            .clone()
+            .next()
+            .filter(|frame| !is_command(frame));
+        if response.is_some() {
+            self.frames.next();
+        }
+        let response = response.map(|frame| {
+            let response = decode_response(frame)?;
+            if frame[1] != command[2] {
+                return Err(MHZ19Error::WrongPacketType(command[2], frame[1]));
+            }
+            Ok(response)
+        });
+        Some(Ok(Exchange { command, response }))
+    }
+}
+
+/// Does the frame look like a command rather than a response?
+fn is_command(frame: &[u8]) -> bool {
+    Command::from_command_value(frame[2]).is_some()
+        && Command::from_command_value(frame[1]).is_none()
+}
+
+/// Parse all the exchanges of a session log, see [`SessionReader`].
+#[cfg(feature = "alloc")]
+pub fn parse_session(log: &[u8]) -> Vec<Result<Exchange, MHZ19Error>> {
+    SessionReader::new(log).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{read_firmware_version, read_gas_concentration, set_detection_range};
+
+    static GAS_CONCENTRATION_RESPONSE: Packet =
+        [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+    static FIRMWARE_VERSION_RESPONSE: Packet =
+        [0xFF, 0xA0, 0x30, 0x34, 0x34, 0x33, 0x00, 0x00, 0x95];
+
+    #[test]
+    fn test_decode_response() {
+        assert_eq!(
+            Ok(Response::GasReading(GasReading {
+                co2_ppm: 608,
+                temperature_celsius: 31,
+                status: 0
+            })),
+            decode_response(&GAS_CONCENTRATION_RESPONSE)
+        );
+        let ack = [0xFF, 0x99, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x67];
+        assert_eq!(
+            Ok(Response::Other(ResponsePacket::from(ack))),
+            decode_response(&ack)
+        );
+        assert_eq!(
+            Err(MHZ19Error::WrongPacketLength(3)),
+            decode_response(&ack[..3])
+        );
+    }
+
+    #[test]
+    fn test_session_reader() {
+        let mut log = [0u8; 9 * 5 + 2];
+        log[..9].copy_from_slice(read_gas_concentration(1).as_ref());
+        log[9..18].copy_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        log[18..27].copy_from_slice(read_firmware_version(1).as_ref());
+        log[27..36].copy_from_slice(&FIRMWARE_VERSION_RESPONSE);
+        // unanswered command, followed by an incomplete frame
+        log[36..45].copy_from_slice(set_detection_range(1, 2000).as_ref());
+        log[45..].copy_from_slice(&[0xFF, 0x99]);
+
+        let mut reader = SessionReader::new(&log);
+        assert_eq!(
+            Some(Ok(Exchange {
+                command: read_gas_concentration(1),
+                response: Some(decode_response(&GAS_CONCENTRATION_RESPONSE))
+            })),
+            reader.next()
+        );
+        assert_eq!(
+            Some(Ok(Exchange {
+                command: read_firmware_version(1),
+                response: Some(Ok(Response::FirmwareVersion(FirmwareVersion {
+                    major: 4,
+                    minor: 43,
+                    patch: 0,
+                })))
+            })),
+            reader.next()
+        );
+        assert_eq!(
+            Some(Ok(Exchange {
+                command: set_detection_range(1, 2000),
+                response: None
+            })),
+            reader.next()
+        );
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn test_session_reader_errors() {
+        let mut log = [0u8; 27];
+        log[..9].copy_from_slice(read_firmware_version(1).as_ref());
+        log[9..18].copy_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        log[18..].copy_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        let mut reader = SessionReader::new(&log);
+        assert_eq!(
+            Some(Ok(Exchange {
+                command: read_firmware_version(1),
+                response: Some(Err(MHZ19Error::WrongPacketType(0xA0, 0x86)))
+            })),
+            reader.next()
+        );
+        assert_eq!(Some(Err(MHZ19Error::WrongStartByte(0x00))), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn test_session_reader_missing_response() {
+        let mut log = [0u8; 9 * 5];
+        log[..9].copy_from_slice(read_gas_concentration(1).as_ref());
+        log[9..18].copy_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        // response missing from the log
+        log[18..27].copy_from_slice(set_detection_range(1, 2000).as_ref());
+        log[27..36].copy_from_slice(read_firmware_version(1).as_ref());
+        log[36..].copy_from_slice(&FIRMWARE_VERSION_RESPONSE);
+
+        let mut reader = SessionReader::new(&log).map(|exchange| {
+            let exchange = exchange.unwrap();
+            (exchange.command, exchange.response.map(|r| r.is_ok()))
+        });
+        assert_eq!(Some((read_gas_concentration(1), Some(true))), reader.next());
+        assert_eq!(Some((set_detection_range(1, 2000), None)), reader.next());
+        assert_eq!(Some((read_firmware_version(1), Some(true))), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_parse_session() {
+        let mut log = read_gas_concentration(1).to_vec();
+        log.extend_from_slice(&GAS_CONCENTRATION_RESPONSE);
+        assert_eq!(1, parse_session(&log).len());
+        assert!(parse_session(&[]).is_empty());
+    }
+}
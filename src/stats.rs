@@ -0,0 +1,599 @@
+//! Statistics over series of CO2 concentration readings, without heap allocation.
+
+/// Compute the p-th percentile of readings sorted in ascending order, using linear
+/// interpolation between the closest ranks (rounded down to the nearest ppm).
+///
+/// Returns `None` if the slice is empty or `p` is greater than 100.
+pub fn percentile(sorted_readings: &[u32], p: u8) -> Option<u32> {
+    if sorted_readings.is_empty() || p > 100 {
+        return None;
+    }
+    let position = p as u64 * (sorted_readings.len() as u64 - 1);
+    let index = (position / 100) as usize;
+    let fraction = position % 100;
+    let lower = sorted_readings[index] as u64;
+    if fraction == 0 {
+        return Some(lower as u32);
+    }
+    let upper = sorted_readings[index + 1] as u64;
+    Some((lower + (upper.saturating_sub(lower)) * fraction / 100) as u32)
+}
+
+/// Is the 95th percentile of readings sorted in ascending order above the threshold?
+///
+/// Returns `false` if the slice is empty.
+pub fn p95_exceeded(readings: &[u32], threshold: u32) -> bool {
+    percentile(readings, 95).is_some_and(|p95| p95 > threshold)
+}
+
+/// Rates of change, in ppm per second, between consecutive `(time, ppm)` samples.
+///
+/// Yields one rate per pair of samples; pairs without elapsed time (same or earlier instant)
+/// are skipped rather than yielding an infinite rate.
+#[cfg(feature = "std")]
+pub fn rates<I: Iterator<Item = (std::time::Instant, u32)>>(iter: I) -> impl Iterator<Item = f32> {
+    iter.scan(None, |previous, (instant, ppm)| {
+        let rate = previous.and_then(
+            |(previous_instant, previous_ppm): (std::time::Instant, u32)| {
+                let elapsed = instant
+                    .saturating_duration_since(previous_instant)
+                    .as_secs_f32();
+                if elapsed > 0.0 {
+                    Some((ppm as f32 - previous_ppm as f32) / elapsed)
+                } else {
+                    None
+                }
+            },
+        );
+        *previous = Some((instant, ppm));
+        Some(rate)
+    })
+    .flatten()
+}
+
+/// Exponential moving average of successive readings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ema {
+    alpha: f32,
+    value: Option<f32>,
+}
+
+impl Ema {
+    /// Create a moving average giving the weight `alpha` (between 0 excluded and 1) to each new
+    /// reading: the smaller, the smoother.
+    pub fn new(alpha: f32) -> Self {
+        debug_assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be in ]0, 1]");
+        Ema { alpha, value: None }
+    }
+
+    /// Blend a new reading with the previous average, the first reading being used as is.
+    pub fn update(&mut self, ppm: u32) -> f32 {
+        let ppm = ppm as f32;
+        let value = match self.value {
+            Some(previous) => previous + self.alpha * (ppm - previous),
+            None => ppm,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    /// Current average, `None` before the first reading
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+}
+
+/// Cumulative CO2 exposure above a baseline: the area under the CO2 curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CO2Budget {
+    pub baseline_ppm: u32,
+    integral: u64,
+    seconds_above_baseline: u64,
+}
+
+impl CO2Budget {
+    pub fn new(baseline_ppm: u32) -> Self {
+        CO2Budget {
+            baseline_ppm,
+            integral: 0,
+            seconds_above_baseline: 0,
+        }
+    }
+
+    /// Account for a concentration measured during the given duration: adds
+    /// `max(0, ppm - baseline_ppm) * duration_seconds` to the integral.
+    pub fn integrate(&mut self, ppm: u32, duration_seconds: u32) {
+        let excess = ppm.saturating_sub(self.baseline_ppm) as u64;
+        if excess > 0 {
+            self.integral = self
+                .integral
+                .saturating_add(excess * duration_seconds as u64);
+            self.seconds_above_baseline += duration_seconds as u64;
+        }
+    }
+
+    /// Reset the exposure, eg at the beginning of each day
+    pub fn reset_daily(&mut self) {
+        self.integral = 0;
+        self.seconds_above_baseline = 0;
+    }
+
+    /// Cumulative exposure in ppm.seconds
+    pub fn integral(&self) -> u64 {
+        self.integral
+    }
+
+    pub fn seconds_above_baseline(&self) -> u64 {
+        self.seconds_above_baseline
+    }
+
+    /// Cumulative exposure in ppm.hours
+    pub fn exposure_ppm_hours(&self) -> f32 {
+        self.integral as f32 / 3600.0
+    }
+}
+
+/// Detection of a stuck sensor, reporting the same concentration over and over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlatlineDetector {
+    /// Number of identical consecutive readings for the sensor to be considered flatlined
+    pub window: usize,
+    last: Option<u32>,
+    identical: usize,
+}
+
+impl FlatlineDetector {
+    pub fn new(window: usize) -> Self {
+        FlatlineDetector {
+            window,
+            last: None,
+            identical: 0,
+        }
+    }
+
+    pub fn push(&mut self, ppm: u32) {
+        if self.last == Some(ppm) {
+            self.identical = self.identical.saturating_add(1);
+        } else {
+            self.last = Some(ppm);
+            self.identical = 1;
+        }
+    }
+
+    /// Are the last `window` readings all equal?
+    ///
+    /// Returns `false` until `window` readings have been pushed.
+    pub fn is_flatlined(&self) -> bool {
+        self.identical > 0 && self.identical >= self.window
+    }
+}
+
+/// Drift of a sensor estimated from the concentrations it reported just before its zero
+/// calibrations, when the actual concentration was known to be `reference_ppm`.
+///
+/// Keeps the offsets of the last `N` calibrations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriftTracker<const N: usize> {
+    pub reference_ppm: u32,
+    offsets: Ring<i32, N>,
+}
+
+impl<const N: usize> Default for DriftTracker<N> {
+    fn default() -> Self {
+        Self::new(400)
+    }
+}
+
+impl<const N: usize> DriftTracker<N> {
+    pub fn new(reference_ppm: u32) -> Self {
+        DriftTracker {
+            reference_ppm,
+            offsets: Ring::new(0),
+        }
+    }
+
+    /// Record the concentration read just before a zero calibration, dropping the oldest one
+    /// if the history is full.
+    pub fn record_before_zero_calibration(&mut self, ppm: u32) {
+        self.offsets.push(crate::ppm_delta(self.reference_ppm, ppm));
+    }
+
+    /// Differences between the recorded concentrations and `reference_ppm`, oldest first
+    pub fn offsets(&self) -> impl Iterator<Item = i32> + '_ {
+        self.offsets.iter().copied()
+    }
+
+    /// Drift trend in ppm per calibration: the slope of the least squares line fitted to the
+    /// offsets.
+    ///
+    /// Returns `None` with less than 2 recorded calibrations.
+    pub fn drift_ppm_per_calibration(&self) -> Option<f32> {
+        linear_fit(indexed(self.offsets.iter().map(|offset| *offset as f32))).map(|fit| fit.slope)
+    }
+}
+
+/// Least squares line `y = slope * x + intercept`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LinearFit {
+    slope: f32,
+    intercept: f32,
+    /// Coefficient of determination
+    r_squared: f32,
+}
+
+/// Fit a line to points, `None` with less than 2 distinct x.
+fn linear_fit<I: Iterator<Item = (f32, f32)> + Clone>(points: I) -> Option<LinearFit> {
+    let (n, sum_x, sum_y) = points
+        .clone()
+        .fold((0.0, 0.0, 0.0), |(n, sum_x, sum_y), (x, y)| {
+            (n + 1.0, sum_x + x, sum_y + y)
+        });
+    if n < 2.0 {
+        return None;
+    }
+    let (mean_x, mean_y) = (sum_x / n, sum_y / n);
+    let (covariance, variance_x, variance_y) = points.clone().fold(
+        (0.0, 0.0, 0.0),
+        |(covariance, variance_x, variance_y), (x, y)| {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            (
+                covariance + dx * dy,
+                variance_x + dx * dx,
+                variance_y + dy * dy,
+            )
+        },
+    );
+    if variance_x == 0.0 {
+        return None;
+    }
+    let slope = covariance / variance_x;
+    let intercept = mean_y - slope * mean_x;
+    let residuals: f32 = points
+        .map(|(x, y)| {
+            let residual = y - (slope * x + intercept);
+            residual * residual
+        })
+        .sum();
+    let r_squared = if variance_y == 0.0 {
+        1.0
+    } else {
+        1.0 - residuals / variance_y
+    };
+    Some(LinearFit {
+        slope,
+        intercept,
+        r_squared,
+    })
+}
+
+/// Fixed capacity ring of the last `N` values pushed, the oldest being dropped when full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Ring<T, const N: usize> {
+    values: [T; N],
+    start: usize,
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> Ring<T, N> {
+    /// Empty ring, `fill` only initializing the storage
+    pub(crate) const fn new(fill: T) -> Self {
+        Ring {
+            values: [fill; N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, value: T) {
+        if N == 0 {
+            return;
+        }
+        if self.len < N {
+            self.values[(self.start + self.len) % N] = value;
+            self.len += 1;
+        } else {
+            self.values[self.start] = value;
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    /// Values, oldest first
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> + Clone + '_ {
+        (0..self.len).map(move |i| &self.values[(self.start + i) % N])
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Points of evenly spaced values, x being the index of the value
+fn indexed<I: Iterator<Item = f32> + Clone>(values: I) -> impl Iterator<Item = (f32, f32)> + Clone {
+    values.enumerate().map(|(i, y)| (i as f32, y))
+}
+
+/// Short term forecast of the concentration, extrapolating the linear trend of the last `N`
+/// readings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CO2Forecast<const N: usize> {
+    /// (seconds since the first reading, ppm)
+    readings: Ring<(u32, u32), N>,
+    elapsed_seconds: u32,
+}
+
+impl<const N: usize> Default for CO2Forecast<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> CO2Forecast<N> {
+    pub fn new() -> Self {
+        CO2Forecast {
+            readings: Ring::new((0, 0)),
+            elapsed_seconds: 0,
+        }
+    }
+
+    /// Record a reading made `elapsed_seconds` after the previous one, dropping the oldest
+    /// reading if the window is full.
+    pub fn push(&mut self, ppm: u32, elapsed_seconds: u32) {
+        if self.readings.len() > 0 {
+            self.elapsed_seconds = self.elapsed_seconds.saturating_add(elapsed_seconds);
+        }
+        self.readings.push((self.elapsed_seconds, ppm));
+    }
+
+    fn fit(&self) -> Option<LinearFit> {
+        linear_fit(
+            self.readings
+                .iter()
+                .map(|(seconds, ppm)| (*seconds as f32 / 60.0, *ppm as f32)),
+        )
+    }
+
+    /// Estimated time before the concentration reaches `threshold`, 0 if it is already reached.
+    ///
+    /// Returns `None` if the concentration is stable or decreasing, or with less than 2 readings.
+    pub fn minutes_to_threshold(&self, threshold: u32) -> Option<f32> {
+        let fit = self.fit()?;
+        if fit.slope <= 0.0 {
+            return None;
+        }
+        let now = self.elapsed_seconds as f32 / 60.0;
+        let current = fit.slope * now + fit.intercept;
+        Some(((threshold as f32 - current) / fit.slope).max(0.0))
+    }
+
+    /// R² of the linear fit, between 0 (unreliable forecast) and 1; 0 with less than 2 readings
+    pub fn confidence(&self) -> f32 {
+        self.fit().map_or(0.0, |fit| fit.r_squared.max(0.0))
+    }
+}
+
+/// Direction of the concentration, see [`TrendDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Stable,
+}
+
+/// Direction of the concentration over the last `N` readings, eg for an up / down arrow on a
+/// display.
+///
+/// The readings are expected to be evenly spaced: the trend is the slope of the least squares
+/// line fitted to them, compared to a threshold in ppm per reading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrendDetector<const N: usize> {
+    readings: Ring<u32, N>,
+    /// Minimum slope, in ppm per reading, for the concentration to be rising or falling
+    pub threshold: f32,
+}
+
+impl<const N: usize> TrendDetector<N> {
+    pub fn new(threshold: f32) -> Self {
+        TrendDetector {
+            readings: Ring::new(0),
+            threshold,
+        }
+    }
+
+    /// Record a reading, dropping the oldest one if the window is full.
+    pub fn push(&mut self, ppm: u32) {
+        self.readings.push(ppm);
+    }
+
+    /// Trend of the recorded readings, `Stable` with less than 2 readings.
+    pub fn trend(&self) -> Trend {
+        let fit = linear_fit(indexed(self.readings.iter().map(|ppm| *ppm as f32)));
+        match fit {
+            Some(fit) if fit.slope >= self.threshold => Trend::Rising,
+            Some(fit) if fit.slope <= -self.threshold => Trend::Falling,
+            _ => Trend::Stable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let readings = [400, 500, 600, 700, 800];
+        assert_eq!(Some(400), percentile(&readings, 0));
+        assert_eq!(Some(600), percentile(&readings, 50));
+        assert_eq!(Some(800), percentile(&readings, 100));
+        assert_eq!(Some(448), percentile(&readings, 12)); // rank 0.48
+        assert_eq!(Some(780), percentile(&readings, 95)); // rank 3.8
+        assert_eq!(Some(412), percentile(&[412], 42));
+        assert_eq!(None, percentile(&[], 50));
+        assert_eq!(None, percentile(&readings, 101));
+    }
+
+    #[test]
+    fn test_p95_exceeded() {
+        let readings = [400, 500, 600, 700, 800];
+        assert!(p95_exceeded(&readings, 700));
+        assert!(!p95_exceeded(&readings, 780));
+        assert!(!p95_exceeded(&[], 0));
+    }
+
+    #[test]
+    fn test_ema() {
+        let mut ema = Ema::new(0.5);
+        assert_eq!(None, ema.value());
+        assert_eq!(400.0, ema.update(400));
+        assert_eq!(500.0, ema.update(600));
+        assert_eq!(550.0, ema.update(600));
+        assert_eq!(475.0, ema.update(400));
+        assert_eq!(Some(475.0), ema.value());
+
+        let mut ema = Ema::new(1.0);
+        ema.update(400);
+        assert_eq!(1000.0, ema.update(1000));
+    }
+
+    #[test]
+    fn test_co2_budget() {
+        let mut budget = CO2Budget::new(400);
+        budget.integrate(1000, 3600);
+        budget.integrate(300, 600);
+        budget.integrate(400, 600);
+        budget.integrate(700, 1800);
+        assert_eq!(600 * 3600 + 300 * 1800, budget.integral());
+        assert_eq!(5400, budget.seconds_above_baseline());
+        assert_eq!(750.0, budget.exposure_ppm_hours());
+
+        budget.reset_daily();
+        assert_eq!(CO2Budget::new(400), budget);
+    }
+
+    #[test]
+    fn test_flatline_detector() {
+        let mut detector = FlatlineDetector::new(3);
+        assert!(!detector.is_flatlined());
+        detector.push(600);
+        detector.push(600);
+        assert!(!detector.is_flatlined());
+        detector.push(600);
+        assert!(detector.is_flatlined());
+        detector.push(600);
+        assert!(detector.is_flatlined());
+
+        detector.push(601);
+        assert!(!detector.is_flatlined());
+        for ppm in [600, 601, 600, 601] {
+            detector.push(ppm);
+            assert!(!detector.is_flatlined());
+        }
+    }
+
+    #[test]
+    fn test_drift_tracker() {
+        let mut tracker = DriftTracker::<3>::default();
+        assert_eq!(None, tracker.drift_ppm_per_calibration());
+        tracker.record_before_zero_calibration(380);
+        assert_eq!(None, tracker.drift_ppm_per_calibration());
+        tracker.record_before_zero_calibration(400);
+        assert_eq!(Some(20.0), tracker.drift_ppm_per_calibration());
+        tracker.record_before_zero_calibration(440);
+        assert_eq!(Some(30.0), tracker.drift_ppm_per_calibration());
+        // the oldest calibration is dropped
+        tracker.record_before_zero_calibration(450);
+        assert_eq!(offsets_array(&tracker), [0, 40, 50]);
+        assert_eq!(Some(25.0), tracker.drift_ppm_per_calibration());
+    }
+
+    fn offsets_array<const N: usize>(tracker: &DriftTracker<N>) -> [i32; N] {
+        let mut offsets = [0; N];
+        for (i, offset) in tracker.offsets().enumerate() {
+            offsets[i] = offset;
+        }
+        offsets
+    }
+
+    #[test]
+    fn test_co2_forecast() {
+        let mut forecast = CO2Forecast::<4>::new();
+        assert_eq!(None, forecast.minutes_to_threshold(1000));
+        assert_eq!(0.0, forecast.confidence());
+        forecast.push(600, 0);
+        assert_eq!(None, forecast.minutes_to_threshold(1000));
+
+        // +50 ppm per minute
+        forecast.push(650, 60);
+        forecast.push(700, 60);
+        assert_eq!(Some(6.0), forecast.minutes_to_threshold(1000));
+        assert_eq!(Some(0.0), forecast.minutes_to_threshold(700));
+        assert_eq!(1.0, forecast.confidence());
+
+        // noisy readings lower the confidence
+        forecast.push(690, 60);
+        assert!(forecast.confidence() < 1.0);
+        assert!(forecast.confidence() > 0.5);
+
+        // the window only keeps the last 4 readings: decreasing trend
+        forecast.push(600, 60);
+        forecast.push(500, 60);
+        assert_eq!(None, forecast.minutes_to_threshold(1000));
+
+        let mut stable = CO2Forecast::<3>::default();
+        for _ in 0..3 {
+            stable.push(600, 30);
+        }
+        assert_eq!(None, stable.minutes_to_threshold(1000));
+        assert_eq!(1.0, stable.confidence());
+    }
+
+    #[test]
+    fn test_trend_detector() {
+        let mut detector = TrendDetector::<4>::new(5.0);
+        assert_eq!(Trend::Stable, detector.trend());
+        for ppm in [400, 420, 430, 450] {
+            detector.push(ppm);
+        }
+        assert_eq!(Trend::Rising, detector.trend());
+
+        // noise below the threshold
+        for ppm in [452, 448, 451, 449] {
+            detector.push(ppm);
+        }
+        assert_eq!(Trend::Stable, detector.trend());
+
+        // only the last 4 readings count
+        for ppm in [900, 800, 700, 650, 600] {
+            detector.push(ppm);
+        }
+        assert_eq!(Trend::Falling, detector.trend());
+
+        let mut empty = TrendDetector::<0>::new(5.0);
+        empty.push(400);
+        assert_eq!(Trend::Stable, empty.trend());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rates() {
+        use std::time::{Duration, Instant};
+
+        let start = Instant::now();
+        let samples = [
+            (start, 400),
+            (start + Duration::from_secs(10), 450),
+            (start + Duration::from_secs(10), 500),
+            (start + Duration::from_secs(30), 420),
+        ];
+        assert_eq!(
+            vec![5.0, -4.0],
+            rates(samples.iter().copied()).collect::<Vec<_>>()
+        );
+        assert_eq!(None, rates(samples[..1].iter().copied()).next());
+    }
+}
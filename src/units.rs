@@ -0,0 +1,103 @@
+//! Physical models built on the readings, using floating point (requires the `units` feature).
+
+use crate::DetectionRange;
+
+/// CO2 generation rate of an adult at rest, in m³/h
+pub const TYPICAL_CO2_GENERATION_RATE: f32 = 0.018;
+
+/// Outdoor air ventilation rate, in m³/h, keeping a room at `co2_ppm` at steady state.
+///
+/// Pettenkofer mass balance: the CO2 generated by the occupants (`num_people` times
+/// `co2_per_person_m3_per_hour`, see [`TYPICAL_CO2_GENERATION_RATE`]) is evacuated by the
+/// outdoor air, each m³ of which carries away `co2_ppm - outdoor_ppm` ppm.
+///
+/// Returns infinity when the indoor concentration is not above the outdoor one, unless nobody
+/// is in the room.
+pub fn required_ventilation_m3_per_hour(
+    co2_ppm: u32,
+    outdoor_ppm: u32,
+    num_people: u32,
+    co2_per_person_m3_per_hour: f32,
+) -> f32 {
+    let generation = num_people as f32 * co2_per_person_m3_per_hour;
+    if generation == 0.0 {
+        return 0.0;
+    }
+    let excess = co2_ppm.saturating_sub(outdoor_ppm);
+    if excess == 0 {
+        return f32::INFINITY;
+    }
+    generation / (excess as f32 * 1e-6)
+}
+
+/// Analog output voltage at 0 ppm
+const ANALOG_ZERO_VOLTAGE: f32 = 0.4;
+/// Analog output voltage at the top of the detection range
+const ANALOG_FULL_SCALE_VOLTAGE: f32 = 2.0;
+
+/// Voltage of the analog output (MH-Z19B) for a concentration, to cross-check it with the uart
+/// readings.
+///
+/// The datasheet maps linearly 0 ppm to 0.4V and the top of the detection range to 2V;
+/// concentrations above the range give 2V.
+pub fn ppm_to_analog_voltage(ppm: u32, detection_range: DetectionRange) -> f32 {
+    let range = detection_range.value() as f32;
+    let ratio = (ppm as f32 / range).min(1.0);
+    ANALOG_ZERO_VOLTAGE + ratio * (ANALOG_FULL_SCALE_VOLTAGE - ANALOG_ZERO_VOLTAGE)
+}
+
+/// Concentration matching a voltage of the analog output, the inverse of
+/// [`ppm_to_analog_voltage`].
+///
+/// Voltages outside of 0.4V to 2V are clamped to the detection range.
+pub fn analog_voltage_to_ppm(voltage: f32, detection_range: DetectionRange) -> u32 {
+    let range = detection_range.value() as f32;
+    let ratio = (voltage - ANALOG_ZERO_VOLTAGE) / (ANALOG_FULL_SCALE_VOLTAGE - ANALOG_ZERO_VOLTAGE);
+    // float to int casts saturate: negative values and NaN give 0
+    ((ratio * range).min(range) + 0.5) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_required_ventilation() {
+        assert_eq!(
+            60.0,
+            required_ventilation_m3_per_hour(1000, 400, 2, TYPICAL_CO2_GENERATION_RATE).round()
+        );
+        assert_eq!(
+            180.0,
+            required_ventilation_m3_per_hour(700, 400, 3, TYPICAL_CO2_GENERATION_RATE).round()
+        );
+        assert_eq!(
+            f32::INFINITY,
+            required_ventilation_m3_per_hour(400, 400, 1, TYPICAL_CO2_GENERATION_RATE)
+        );
+        assert_eq!(
+            0.0,
+            required_ventilation_m3_per_hour(400, 400, 0, TYPICAL_CO2_GENERATION_RATE)
+        );
+    }
+
+    #[test]
+    fn test_analog_voltage() {
+        assert_eq!(0.4, ppm_to_analog_voltage(0, DetectionRange::Ppm5000));
+        assert_eq!(1.2, ppm_to_analog_voltage(2500, DetectionRange::Ppm5000));
+        assert_eq!(2.0, ppm_to_analog_voltage(2000, DetectionRange::Ppm2000));
+        assert_eq!(2.0, ppm_to_analog_voltage(3000, DetectionRange::Ppm2000));
+
+        assert_eq!(2500, analog_voltage_to_ppm(1.2, DetectionRange::Ppm5000));
+        assert_eq!(1000, analog_voltage_to_ppm(1.2, DetectionRange::Ppm2000));
+        assert_eq!(0, analog_voltage_to_ppm(0.1, DetectionRange::Ppm2000));
+        assert_eq!(2000, analog_voltage_to_ppm(2.5, DetectionRange::Ppm2000));
+        for ppm in [0, 400, 1234, 9999, 10000] {
+            let voltage = ppm_to_analog_voltage(ppm, DetectionRange::Ppm10000);
+            assert_eq!(
+                ppm,
+                analog_voltage_to_ppm(voltage, DetectionRange::Ppm10000)
+            );
+        }
+    }
+}
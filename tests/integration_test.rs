@@ -0,0 +1,109 @@
+//! End to end test of the serial "api" over a virtual serial port.
+//!
+//! `socat` is used to create a pair of linked pseudo terminals: the test writes commands
+//! on one end while a fake sensor answers on the other end. The test is skipped when `socat`
+//! is not available.
+#![cfg(unix)]
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Canned response of the fake sensor: 0x0260 = 608 ppm
+const GAS_CONCENTRATION_RESPONSE: [u8; 9] = [0xFF, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xD1];
+
+fn socat_available() -> bool {
+    Command::new("which")
+        .arg("socat")
+        .stdout(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// socat process, killed on drop.
+struct VirtualSerialPort {
+    socat: Child,
+    host_path: String,
+    sensor_path: String,
+}
+
+impl VirtualSerialPort {
+    fn spawn() -> VirtualSerialPort {
+        let mut socat = Command::new("socat")
+            .args(["-d", "-d", "pty,raw,echo=0", "pty,raw,echo=0"])
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Unable to launch socat");
+        // socat logs the allocated ptys as "... N PTY is /dev/pts/X"
+        let mut paths = BufReader::new(socat.stderr.take().unwrap())
+            .lines()
+            .map(|line| line.expect("Unable to read socat output"))
+            .filter_map(|line| line.split("PTY is ").nth(1).map(str::to_string));
+        let host_path = paths.next().expect("socat did not allocate a pty");
+        let sensor_path = paths.next().expect("socat did not allocate a pty");
+        // keep draining the logs, socat would die of a broken pipe otherwise
+        thread::spawn(move || paths.for_each(drop));
+        VirtualSerialPort {
+            socat,
+            host_path,
+            sensor_path,
+        }
+    }
+
+    fn open(path: &str) -> File {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .expect("Unable to open pty")
+    }
+}
+
+impl Drop for VirtualSerialPort {
+    fn drop(&mut self) {
+        let _ = self.socat.kill();
+        let _ = self.socat.wait();
+    }
+}
+
+#[test]
+fn read_gas_concentration_over_virtual_serial_port() {
+    if !socat_available() {
+        eprintln!("socat not found in $PATH, skipping");
+        return;
+    }
+    let port = VirtualSerialPort::spawn();
+    let mut host = VirtualSerialPort::open(&port.host_path);
+    let mut sensor = VirtualSerialPort::open(&port.sensor_path);
+
+    // fake sensor
+    let sensor_thread = thread::spawn(move || {
+        let mut command = [0u8; 9];
+        sensor.read_exact(&mut command).unwrap();
+        sensor.write_all(&GAS_CONCENTRATION_RESPONSE).unwrap();
+        command
+    });
+
+    // reads may block forever if something goes wrong, do the exchange in a thread
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        host.write_all(mh_z19::read_gas_concentration(1).as_ref())
+            .unwrap();
+        let mut response = [0u8; 9];
+        host.read_exact(&mut response).unwrap();
+        tx.send(response).unwrap();
+    });
+    let response = rx
+        .recv_timeout(Duration::from_secs(5))
+        .expect("No response received from the fake sensor");
+
+    assert_eq!(
+        mh_z19::read_gas_concentration(1),
+        sensor_thread.join().unwrap()
+    );
+    assert_eq!(Ok(608), mh_z19::parse_gas_concentration_ppm(&response));
+}